@@ -1,4 +1,11 @@
+use crate::steam;
 
+pub const SUPERHEATING_HEAT_TRANSFER: f64 = 16000000.0; // J/t per superheating element
+const WATER_PER_VOLUME: i64 = 16000; // mB of water tank per block of water cavity
+const STEAM_PER_VOLUME: i64 = 160000; // mB of steam tank per block of steam cavity
+const COOLANT_PER_VOLUME: i64 = 256000; // mB of superheated or cooled coolant tank per block of cavity
+
+#[derive(Debug, Clone)]
 pub struct Boiler {
     pub x: i32,
     pub z: i32,
@@ -12,12 +19,44 @@ impl Boiler {
     #[allow(dead_code)]
     pub fn print(&self){
         println!("A {}x{}x{} Boiler", self.x, self.z, self.y);
-        println!("- Super Heating Elements {}", self.heating_element);
+        println!("- Super Heating Elements {}, Pressure Dispersers {}", self.heating_element, self.dispersers);
+        println!("- Water Cavity {} blocks, Steam Cavity {} blocks", self.water_volume(), self.steam_volume());
+        println!("- Max Boil Rate {} mB/t", self.max_boil_rate());
     }
 
     pub fn summarize(&self) -> String {
         format!("{}x{}x{} Boiler", self.x, self.z, self.y)
     }
+
+    /// Y of the pressure disperser layer, right above the superheating elements with at least one layer of water below
+    pub fn disperser_layer(&self) -> i32 {
+        let area = (self.x - 2) * (self.z - 2);
+        if area <= 0 {
+            return 2;
+        }
+        let element_layers = (self.heating_element + area - 1) / area;
+        (element_layers + 1).max(2)
+    }
+
+    /// Blocks of water cavity, everything under the dispersers that isn't a superheating element
+    pub fn water_volume(&self) -> i32 {
+        let area = (self.x - 2) * (self.z - 2);
+        ((self.disperser_layer() - 1) * area - self.heating_element).max(0)
+    }
+
+    /// Blocks of steam cavity above the dispersers
+    pub fn steam_volume(&self) -> i32 {
+        let area = (self.x - 2) * (self.z - 2);
+        ((self.y - 2 - self.disperser_layer()) * area).max(0)
+    }
+
+    /// Steam boiled every tick, limited by the superheating elements and by the water and steam the boiler can hold
+    pub fn max_boil_rate(&self) -> i32 {
+        let heat_limit = steam::steam_from_heat(self.heating_element as f64 * SUPERHEATING_HEAT_TRANSFER) as i64;
+        let water_limit = self.water_volume() as i64 * WATER_PER_VOLUME;
+        let steam_limit = self.steam_volume() as i64 * STEAM_PER_VOLUME;
+        heat_limit.min(water_limit).min(steam_limit) as i32
+    }
 }
 
 impl Default for Boiler {
@@ -52,4 +91,58 @@ pub fn optimal_boiler_with_dimensions(x: i32, z: i32, y: i32) -> Boiler {
         println!("Reactor height too large, max 18 blocks.");
     }
     Boiler { ..Default::default()}
-}
\ No newline at end of file
+}
+
+/// Smallest boiler taking `heat` J/t from `coolant_flow` mB/t of superheated coolant, like a sodium cooled reactor's.
+/// Enough superheating elements for all the heat, and cavities big enough for the water, steam and coolant.
+pub fn boiler_for_heat(heat: f64, coolant_flow: i32) -> Result<Boiler, &'static str> {
+    let heating_element = (heat / SUPERHEATING_HEAT_TRANSFER).ceil() as i32;
+    let steam = steam::steam_from_heat(heat);
+    let mut best: Option<Boiler> = None;
+    for x in 3..=18 {
+        for z in x..=18 {
+            for y in 4..=18 {
+                let boiler = Boiler { x, z, y, heating_element, dispersers: (x - 2) * (z - 2) };
+                if boiler.max_boil_rate() < steam
+                    || (boiler.water_volume() as i64 * COOLANT_PER_VOLUME) < coolant_flow as i64
+                    || (boiler.steam_volume() as i64 * COOLANT_PER_VOLUME) < coolant_flow as i64 {
+                    continue;
+                }
+                let smaller = match &best {
+                    None => true,
+                    Some(best) => x * z * y < best.x * best.z * best.y,
+                };
+                if smaller {
+                    best = Some(boiler);
+                }
+                // Taller only gets bigger
+                break;
+            }
+        }
+    }
+    best.ok_or("No boiler up to 18x18x18 can take that much heat.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn test_boiler_for_heat() {
+        // Sodium cooled 5x6x5, 12 MJ/t carried by 2,400,000 mB/t of superheated sodium
+        let mut reactor = utils::get_optimal_reactor(5,6,5);
+        reactor.coolant = crate::fission::Coolant::Sodium;
+        let actual = boiler_for_heat(reactor.heat_output(), reactor.coolant_flow()).unwrap();
+        assert_eq!(actual.heating_element, 1);
+        assert!(actual.max_boil_rate() >= reactor.water_burn_rate);
+        assert!(actual.water_volume() as i64 * COOLANT_PER_VOLUME >= reactor.coolant_flow() as i64);
+        assert!(actual.steam_volume() as i64 * COOLANT_PER_VOLUME >= reactor.coolant_flow() as i64);
+        // Nothing one smaller in any direction fits
+        for smaller in [Boiler { x: actual.x - 1, ..actual.clone() }, Boiler { y: actual.y - 1, ..actual.clone() }] {
+            assert!(smaller.max_boil_rate() < reactor.water_burn_rate
+                || smaller.steam_volume() as i64 * COOLANT_PER_VOLUME < reactor.coolant_flow() as i64);
+        }
+        assert!(boiler_for_heat(1e12, 0).is_err());
+    }
+}
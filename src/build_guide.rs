@@ -0,0 +1,72 @@
+// Layer by layer ASCII build guides, printable for building by hand in survival
+use crate::layout::{Block, Layout};
+
+/// Draw every Y layer of a layout bottom up as a grid, x across and z down, followed by a legend
+pub fn build_guide(layout: &Layout) -> String {
+    let mut guide = String::new();
+    let mut used: Vec<Block> = Vec::new();
+    for y in 0..layout.y {
+        guide.push_str(&format!("Layer {} of {}\n", y + 1, layout.y));
+        for z in 0..layout.z {
+            for x in 0..layout.x {
+                let block = layout.get(x, y, z);
+                if !used.contains(&block) {
+                    used.push(block);
+                }
+                guide.push(block.symbol());
+            }
+            guide.push('\n');
+        }
+        guide.push('\n');
+    }
+    guide.push_str("Legend\n");
+    for block in std::iter::once(Block::Air).chain(Block::ALL) {
+        if used.contains(&block) {
+            guide.push_str(&format!("{} = {}\n", block.symbol(), block.name()));
+        }
+    }
+    guide
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{layout, utils};
+
+    #[test]
+    fn test_turbine_build_guide() {
+        let turbine = utils::get_optimal_turbine(5,5);
+        let guide = build_guide(&layout::turbine_layout(&turbine));
+        let expected_middle = "Layer 2 of 5\n\
+                               CGGGC\n\
+                               G...G\n\
+                               P.R.P\n\
+                               G...G\n\
+                               CGGGC\n";
+        assert!(guide.contains(expected_middle));
+        let expected_dispersers = "Layer 3 of 5\n\
+                                   CCCCC\n\
+                                   CDDDC\n\
+                                   CDXDC\n\
+                                   CDDDC\n\
+                                   CCCCC\n";
+        assert!(guide.contains(expected_dispersers));
+        assert!(guide.contains("R = Turbine Rotor\n"));
+        assert!(!guide.contains("F = Fission Fuel Assembly\n"));
+    }
+
+    #[test]
+    fn test_reactor_build_guide() {
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let guide = build_guide(&layout::fission_layout(&reactor));
+        let expected_top = "Layer 4 of 5\n\
+                            CCCCC\n\
+                            CK.KC\n\
+                            C.K.C\n\
+                            CK.KC\n\
+                            C.K.C\n\
+                            CCCCC\n";
+        assert!(guide.contains(expected_top));
+        assert!(guide.contains("F = Fission Fuel Assembly\nK = Control Rod Assembly\n"));
+    }
+}
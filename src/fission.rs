@@ -1,12 +1,98 @@
 use crate::turbine::Turbine;
 use std::cmp::min;
+use std::fmt;
 use factor::factor;
 use serde::Deserialize;
 // use num_integer::Roots; 
 
-const FUEL_ASSEMBLY_FLUID_BURN_RATE: i32 = 20000; // mb/t of water
+pub const FUEL_ASSEMBLY_FLUID_BURN_RATE: i32 = 20000; // mb/t of water, boiled by the reactor or its boiler
+const FUEL_ASSEMBLY_SODIUM_BURN_RATE: i32 = 200000; // mb/t of sodium, 1 MJ/t of heat over sodium's 5 J/mB
 const CASING_HEAT_CAPACITY: i32 = 1000;
+const FUEL_PER_ASSEMBLY: i32 = 8000; // mB of fuel tank, and as much waste tank, per fuel assembly
+const TICKS_PER_SECOND: f64 = 20.0;
 const FISSION_SURFACE_AREA_TARGET: f32 = 4.0;
+pub const ENERGY_PER_FISSION_FUEL: f64 = 1000000.0; // J per mB of fissile fuel burned
+pub const AMBIENT_TEMPERATURE: f64 = 300.0; // K
+pub const BASE_BOIL_TEMPERATURE: f64 = 373.15; // K
+const AIR_INVERSE_COEFFICIENT: f64 = 10000.0;
+const CASING_INVERSE_INSULATION_COEFFICIENT: f64 = 10000.0;
+const CASING_INVERSE_CONDUCTION_COEFFICIENT: f64 = 10.0;
+/// Heat leaving the casing to the air is (temperature - ambient) / ENVIRONMENT_INVERSE_CONDUCTION(20,010) of the heat capacity
+pub const ENVIRONMENT_INVERSE_CONDUCTION: f64 =
+    AIR_INVERSE_COEFFICIENT + CASING_INVERSE_INSULATION_COEFFICIENT + CASING_INVERSE_CONDUCTION_COEFFICIENT;
+/// Above this the reactor takes damage, below it repairs
+pub const MIN_DAMAGE_TEMPERATURE: f64 = 1200.0; // K
+const WATER_THERMAL_ENTHALPY: f64 = 10.0; // J/mB
+const STEAM_ENERGY_EFFICIENCY: f64 = 0.2;
+const SODIUM_THERMAL_ENTHALPY: f64 = 5.0; // J/mB
+
+/// What the reactor's heat goes into
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Coolant {
+    /// Boiled straight into steam for turbines
+    #[default]
+    Water,
+    /// Heated into superheated sodium, which a boiler turns into steam
+    Sodium,
+}
+
+impl Coolant {
+    #[allow(dead_code)]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Coolant::Water => "Water",
+            Coolant::Sodium => "Sodium",
+        }
+    }
+
+    /// Heat each mB takes away from the reactor, water only turns a fifth of its heat into steam
+    pub fn heat_per_mb(&self) -> f64 {
+        match self {
+            Coolant::Water => WATER_THERMAL_ENTHALPY / STEAM_ENERGY_EFFICIENCY,
+            Coolant::Sodium => SODIUM_THERMAL_ENTHALPY,
+        }
+    }
+}
+
+/// Pipes feeding coolant into a reactor port, mechanical pipes for water and pressurized tubes for sodium
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PipeTier {
+    Basic,
+    #[allow(dead_code)]
+    Advanced,
+    #[allow(dead_code)]
+    Elite,
+    Ultimate,
+}
+
+impl PipeTier {
+    #[allow(dead_code)]
+    pub const ALL: [PipeTier; 4] = [PipeTier::Basic, PipeTier::Advanced, PipeTier::Elite, PipeTier::Ultimate];
+
+    /// Coolant one pipe carries in mB/t
+    pub fn throughput(&self, coolant: Coolant) -> f64 {
+        match (coolant, self) {
+            (Coolant::Water, PipeTier::Basic) => 2000.0,
+            (Coolant::Water, PipeTier::Advanced) => 8000.0,
+            (Coolant::Water, PipeTier::Elite) => 32000.0,
+            (Coolant::Water, PipeTier::Ultimate) => 128000.0,
+            (Coolant::Sodium, PipeTier::Basic) => 4000.0,
+            (Coolant::Sodium, PipeTier::Advanced) => 16000.0,
+            (Coolant::Sodium, PipeTier::Elite) => 256000.0,
+            (Coolant::Sodium, PipeTier::Ultimate) => 1024000.0,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn name(&self) -> &'static str {
+        match self {
+            PipeTier::Basic => "Basic",
+            PipeTier::Advanced => "Advanced",
+            PipeTier::Elite => "Elite",
+            PipeTier::Ultimate => "Ultimate",
+        }
+    }
+}
 
 /// Fission Reactor Struct, containing info on dimensions, block ammounts, and calculations
 #[derive(Deserialize, Debug, Clone)]
@@ -16,15 +102,20 @@ pub struct FissionReactor {
     pub y: i32,
     pub fuel_assemblies: i32,
     pub control_rods: i32,
-    pub water_burn_rate: i32,  // mb/t
+    pub water_burn_rate: i32,  // mb/t, boiled by the boiler when sodium cooled
     pub heat_capacity: i32,  // J/K
     pub fuel_surface_area: i32,  // m2
-    pub boil_efficiency: f32,  //This one will be hard to model
+    pub boil_efficiency: f32,  // 0 to 1, from fuel surface area
     pub max_burn_rate: i32,  // mB/t
+    /// Fuel assembly stack heights of a reactor described block by block, same indexing as `fuel_columns`
+    #[serde(default)]
+    pub fuel_layout: Vec<i32>,
+    #[serde(default)]
+    pub coolant: Coolant,
     //TODO Need to add the burn rate calculations, like what the coolant flow rate will be
 }
 
-//have this here so I can ignore the broken value fuel_surface_area
+// fuel_layout is left out, a reactor described stack by stack equals the same optimal one
 impl PartialEq for FissionReactor {
     fn eq(&self, other: &Self) -> bool {
         self.x ==  other.x &&
@@ -34,9 +125,10 @@ impl PartialEq for FissionReactor {
         self.control_rods ==  other.control_rods &&
         self.water_burn_rate == other.water_burn_rate &&
         self.heat_capacity == other.heat_capacity &&
-        // self.fuel_surface_area == other.fuel_surface_area &&
+        self.fuel_surface_area == other.fuel_surface_area &&
         self.boil_efficiency == other.boil_efficiency &&
-        self.max_burn_rate == other.max_burn_rate
+        self.max_burn_rate == other.max_burn_rate &&
+        self.coolant == other.coolant
     }
 }
 
@@ -51,8 +143,10 @@ impl Default for FissionReactor {
             water_burn_rate: 0,
             heat_capacity: 0,  // J/K
             fuel_surface_area: 0,  // m2
-            boil_efficiency: 0.0,  //This one will be hard to model
+            boil_efficiency: 0.0,
             max_burn_rate: 0,  // mB/t
+            fuel_layout: Vec::new(),
+            coolant: Coolant::Water,
         }
     }
 }
@@ -63,14 +157,47 @@ impl FissionReactor {
     pub fn print(&self){
         println!("A {}x{}x{} Fission Reactor", self.x, self.z, self.y);
         println!("- Fuel Assemblies {}, and Control Rods {}", self.fuel_assemblies, self.control_rods);
-        println!("- Water Burn Rate {} mb/t", self.water_burn_rate);
+        match self.coolant {
+            Coolant::Water => println!("- Water Burn Rate {} mb/t", self.water_burn_rate),
+            Coolant::Sodium => {
+                println!("- Sodium Burn Rate {} mB/t, heated into as much Superheated Sodium", self.coolant_flow());
+                println!("- Heat to the Boiler {} J/t, boiling {} mb/t of water", self.heat_output(), self.water_burn_rate);
+            }
+        }
         println!("- Fuel Surface Area {} (m2), Boil Efficiency {}", self.fuel_surface_area, self.boil_efficiency);
         println!("- Max Burn Rate {} mB/t", self.max_burn_rate);
+        let sustainable: Vec<String> = PipeTier::ALL
+            .iter()
+            .map(|tier| format!("{:.2} {}", sustainable_burn_rate(self, f64::MAX, *tier), tier.name()))
+            .collect();
+        println!("- Sustainable Burn Rate through one Pipe by Tier: {} mB/t", sustainable.join(", "));
+        let pipes: Vec<String> = PipeTier::ALL
+            .iter()
+            .map(|tier| format!("{} {}", (self.coolant_flow() as f64 / tier.throughput(self.coolant)).ceil(), tier.name()))
+            .collect();
+        println!("- Pipes Needed for Max Burn Rate by Tier: {}", pipes.join(", "));
         println!("- Heat Capacity {} J/K", self.heat_capacity);
+        fuel_usage(self, self.max_burn_rate as f64).print();
     }
 
     pub fn summarize(&self) -> String {
-        format!("A {}x{}x{} Fission Reactor", self.x, self.z, self.y)
+        match self.coolant {
+            Coolant::Water => format!("A {}x{}x{} Fission Reactor", self.x, self.z, self.y),
+            Coolant::Sodium => format!("A {}x{}x{} Sodium Cooled Fission Reactor", self.x, self.z, self.y),
+        }
+    }
+
+    /// Coolant heated every tick at the max burn rate, water boiled into steam or sodium superheated, in mB/t
+    pub fn coolant_flow(&self) -> i32 {
+        match self.coolant {
+            Coolant::Water => self.fuel_assemblies * FUEL_ASSEMBLY_FLUID_BURN_RATE,
+            Coolant::Sodium => self.fuel_assemblies * FUEL_ASSEMBLY_SODIUM_BURN_RATE,
+        }
+    }
+
+    /// Heat the coolant carries away at the max burn rate, J/t
+    pub fn heat_output(&self) -> f64 {
+        self.max_burn_rate as f64 * ENERGY_PER_FISSION_FUEL
     }
 }
 
@@ -97,7 +224,20 @@ pub fn optimal_fission_with_dimensions(x: i32, z: i32, y: i32) -> FissionReactor
     let (fuel_assemblies, control_rods) = fuel_assemblies_dimensions(x, z, y);
     // Is this something the reactor should know about itself?  Also this could be calculated automatically by the struct
     let water_burn_rate = fuel_assemblies * FUEL_ASSEMBLY_FLUID_BURN_RATE;
-    FissionReactor {x, z, y, fuel_assemblies, control_rods, water_burn_rate, ..Default::default()}
+    let mut reactor = FissionReactor {
+        x,
+        z,
+        y,
+        fuel_assemblies,
+        control_rods,
+        water_burn_rate,
+        heat_capacity: heat_capacity(x, z, y),
+        max_burn_rate: fuel_assemblies,
+        ..Default::default()
+    };
+    reactor.fuel_surface_area = fuel_surface_area(&reactor);
+    reactor.boil_efficiency = boil_efficiency(&reactor);
+    reactor
 }
 
 /// Create fission reactor based on max output/max flow from turbine
@@ -141,41 +281,300 @@ pub fn turbine_based_fission_reactor(fuel_assemblies: i32) -> FissionReactor {
     if area >= fuel_assemblies + control_rods {
         reactor.y = reactor.y - 1;
     }
-    // Surface area
-    let mut surface_area = fuel_assemblies * 6;  //306
-    let levels = fuel_assemblies / reactor.control_rods;
-    let mut touching_assemblies = 0;
-    if levels <= 1 {
-        touching_assemblies = 0;
-    } else if levels == 2 {
-        touching_assemblies = reactor.control_rods * 2;
-    } else if levels > 2 {
-        touching_assemblies = reactor.control_rods * 2 + reactor.control_rods * levels - 2;
-    }
-    // let remander = fuel_assemblies - reactor.control_rods * touching_assemblies;
-    // touching_assemblies = touching_assemblies + (remander * 6);
-    surface_area = surface_area - touching_assemblies;
-    // Boil Efficiency Rate
-    let avg_surface_area = surface_area / fuel_assemblies;
-    let mut boil_efficiency = avg_surface_area as f32 / FISSION_SURFACE_AREA_TARGET;
-    if boil_efficiency > 1.0 {
-        boil_efficiency = 1.0;
-    }
     // reactor.x = x;
     // reactor.z = z;
     // reactor.y = y;  // TODO Figure out what the hell do for this 
     // reactor.control_rods = factors[second_value_index + 1] as i32;
     reactor.water_burn_rate = fuel_assemblies * FUEL_ASSEMBLY_FLUID_BURN_RATE;
     reactor.heat_capacity = heat_capacity(reactor.x, reactor.z, reactor.y);
-    reactor.fuel_surface_area = surface_area;
-    reactor.boil_efficiency = boil_efficiency;
     reactor.max_burn_rate = fuel_assemblies;
+    reactor.fuel_surface_area = fuel_surface_area(&reactor);
+    reactor.boil_efficiency = boil_efficiency(&reactor);
     // TODO What do we do with these ratios?
     // TODO How do I calculate the hight.  3,4 is correct for the x,z but y in this case is 2, is it the distance between the two variables.
     // No it shouldnt be cause all should lead to size of 2
     reactor
 }
 
+/// Fissile fuel burned and nuclear waste made at a burn rate, one mB of waste for every mB of fuel
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuelUsage {
+    pub burn_rate: f64,  // mB/t
+    pub fuel_per_second: f64,  // mB/s
+    pub fuel_per_hour: f64,  // mB/h
+    pub waste_per_second: f64,  // mB/s
+    pub waste_per_hour: f64,  // mB/h
+    pub fuel_tank: i32,  // mB
+    pub waste_tank: i32,  // mB
+    pub fuel_tank_seconds: f64,  // A full tank with nothing refilling it
+    pub waste_tank_seconds: f64,  // An empty tank with nothing draining it
+}
+
+impl FuelUsage {
+    #[allow(dead_code)]
+    pub fn print(&self) {
+        println!("At {:.2} mB/t of fuel:", self.burn_rate);
+        println!("- Fissile Fuel {:.2} mB/s, {:.0} mB/h", self.fuel_per_second, self.fuel_per_hour);
+        println!("- Nuclear Waste {:.2} mB/s, {:.0} mB/h", self.waste_per_second, self.waste_per_hour);
+        println!("- Fuel Tank {} mB lasts {:.0} s, Waste Tank {} mB fills in {:.0} s", self.fuel_tank, self.fuel_tank_seconds, self.waste_tank, self.waste_tank_seconds);
+        println!("- Stalls after {:.0} s without logistics\n", self.stall_seconds());
+    }
+
+    /// Seconds until the reactor stops burning, out of fuel or with nowhere to put waste
+    pub fn stall_seconds(&self) -> f64 {
+        self.fuel_tank_seconds.min(self.waste_tank_seconds)
+    }
+}
+
+/// Fuel and waste at `burn_rate` mB/t, never more than the fuel assemblies burn
+pub fn fuel_usage(reactor: &FissionReactor, burn_rate: f64) -> FuelUsage {
+    let burn_rate = burn_rate.clamp(0.0, reactor.max_burn_rate as f64);
+    let per_second = burn_rate * TICKS_PER_SECOND;
+    let tank = reactor.fuel_assemblies * FUEL_PER_ASSEMBLY;
+    let tank_seconds = if per_second > 0.0 { tank as f64 / per_second } else { f64::INFINITY };
+    FuelUsage {
+        burn_rate,
+        fuel_per_second: per_second,
+        fuel_per_hour: per_second * 3600.0,
+        waste_per_second: per_second,
+        waste_per_hour: per_second * 3600.0,
+        fuel_tank: tank,
+        waste_tank: tank,
+        fuel_tank_seconds: tank_seconds,
+        waste_tank_seconds: tank_seconds,
+    }
+}
+
+/// Highest burn rate (mB/t) that keeps the reactor below MIN_DAMAGE_TEMPERATURE, fed `coolant_input` mB/t through one pipe.
+/// Just under that temperature the coolant and the air take away the most heat they can without damage,
+/// the coolant limited by what comes in and by how much heat the casing passes it at the boil efficiency.
+pub fn sustainable_burn_rate(reactor: &FissionReactor, coolant_input: f64, pipe: PipeTier) -> f64 {
+    let heat_capacity = reactor.heat_capacity as f64;
+    let coolant = coolant_input.min(pipe.throughput(reactor.coolant));
+    let coolant_heat = (reactor.boil_efficiency as f64 * (MIN_DAMAGE_TEMPERATURE - BASE_BOIL_TEMPERATURE) * heat_capacity)
+        .min(coolant * reactor.coolant.heat_per_mb());
+    let environment_loss = (MIN_DAMAGE_TEMPERATURE - AMBIENT_TEMPERATURE) / ENVIRONMENT_INVERSE_CONDUCTION * heat_capacity;
+    let burn_rate = (coolant_heat + environment_loss) / ENERGY_PER_FISSION_FUEL;
+    // Set in game in hundredths of a mB/t
+    ((burn_rate * 100.0).floor() / 100.0).min(reactor.max_burn_rate as f64)
+}
+
+/// Fuel assembly stack height for each column inside the reactor, indexed `x + z * (reactor.x - 2)`.
+/// Stacks are spread evenly over a checkerboard so no two touch, each one capped with a control rod.
+pub fn fuel_columns(reactor: &FissionReactor) -> Vec<i32> {
+    let width = reactor.x - 2;
+    let depth = reactor.z - 2;
+    if !reactor.fuel_layout.is_empty() && reactor.fuel_layout.len() as i32 == width * depth {
+        return reactor.fuel_layout.clone();
+    }
+    let max_height = reactor.y - 3;
+    if width <= 0 || depth <= 0 || max_height <= 0 {
+        return Vec::new();
+    }
+    let mut heights = vec![0; (width * depth) as usize];
+    // Checkerboard spots first, the other spots only if there are more rods than that
+    let mut spots: Vec<usize> = (0..width * depth).filter(|i| (i % width + i / width) % 2 == 0).map(|i| i as usize).collect();
+    spots.extend((0..width * depth).filter(|i| (i % width + i / width) % 2 == 1).map(|i| i as usize));
+    let columns = min(reactor.control_rods, width * depth);
+    if columns <= 0 {
+        return heights;
+    }
+    let base = reactor.fuel_assemblies / columns;
+    let remainder = reactor.fuel_assemblies % columns;
+    for (column, spot) in spots.iter().take(columns as usize).enumerate() {
+        let height = if (column as i32) < remainder { base + 1 } else { base };
+        heights[*spot] = min(height, max_height);
+    }
+    heights
+}
+
+/// Exposed faces of every fuel assembly, counted the way Mekanism's FissionReactorValidator does.
+/// Each assembly has 6 faces and two touching assemblies hide one face each, so a lone stack of h is 4h + 2.
+pub fn fuel_surface_area(reactor: &FissionReactor) -> i32 {
+    let width = (reactor.x - 2) as usize;
+    let columns = fuel_columns(reactor);
+    let mut surface_area = 0;
+    for (i, height) in columns.iter().enumerate() {
+        if *height == 0 {
+            continue;
+        }
+        // Own stack, the faces between its assemblies are hidden
+        surface_area += height * 6 - (height - 1) * 2;
+        // Stacks all stand on the floor, so neighbours touch up to the shorter of the two
+        if (i + 1) % width != 0 {
+            surface_area -= min(*height, columns[i + 1]) * 2;
+        }
+        if let Some(behind) = columns.get(i + width) {
+            surface_area -= min(*height, *behind) * 2;
+        }
+    }
+    surface_area
+}
+
+/// Boil Efficiency = min(1, Surface Area / Fuel Assemblies / FISSION_SURFACE_AREA_TARGET(4))
+/// https://github.com/mekanism/Mekanism/blob/a3660901504ef724366224012bcea14be2cb734a/src/generators/java/mekanism/generators/common/content/fission/FissionReactorMultiblockData.java#L471
+pub fn boil_efficiency(reactor: &FissionReactor) -> f32 {
+    let fuel_assemblies: i32 = fuel_columns(reactor).iter().sum();
+    if fuel_assemblies == 0 {
+        return 0.0;
+    }
+    let avg_surface_area = fuel_surface_area(reactor) as f32 / fuel_assemblies as f32;
+    (avg_surface_area / FISSION_SURFACE_AREA_TARGET).min(1.0)
+}
+
+/// Why a described reactor can't be built
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReactorGridError {
+    Empty,
+    UnevenGrid { layer: usize },
+    UnevenRow { row: usize },
+    UnknownBlock { symbol: char },
+    NotANumber { value: String },
+    OutOfRange { x: i32, z: i32, y: i32 },
+    TooTall { x: usize, z: usize, height: i32, max: i32 },
+    FuelWithoutControlRod { x: usize, z: usize },
+    ControlRodWithoutFuel { x: usize, z: usize },
+    SplitColumn { x: usize, z: usize },
+}
+
+impl fmt::Display for ReactorGridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReactorGridError::Empty => write!(f, "Nothing was described."),
+            ReactorGridError::UnevenGrid { layer } => write!(f, "Layer {layer} isn't the same size as the first layer."),
+            ReactorGridError::UnevenRow { row } => write!(f, "Row {row} doesn't have as many columns as the first row."),
+            ReactorGridError::UnknownBlock { symbol } => write!(f, "Unknown block '{symbol}', use F for fuel, K for control rods and . for air."),
+            ReactorGridError::NotANumber { value } => write!(f, "'{value}' isn't a fuel stack height."),
+            ReactorGridError::OutOfRange { x, z, y } => write!(f, "A {x}x{z}x{y} reactor is outside 3x3x4 to 18x18x18."),
+            ReactorGridError::TooTall { x, z, height, max } => write!(f, "Column {x},{z} has {height} fuel assemblies, at most {max} fit with a control rod on top."),
+            ReactorGridError::FuelWithoutControlRod { x, z } => write!(f, "Column {x},{z} has fuel without a control rod on top."),
+            ReactorGridError::ControlRodWithoutFuel { x, z } => write!(f, "Column {x},{z} has a control rod without fuel under it."),
+            ReactorGridError::SplitColumn { x, z } => write!(f, "Column {x},{z} isn't one stack of fuel topped by a control rod."),
+        }
+    }
+}
+
+/// Build a reactor from the fuel stack height of every column inside it, a control rod on top of each stack.
+/// Rows are z and each row's entries x, so the reactor is two blocks wider and deeper than the map.
+pub fn reactor_from_columns(columns: &[Vec<i32>], y: i32) -> Result<FissionReactor, ReactorGridError> {
+    let depth = columns.len();
+    let width = columns.first().map_or(0, |row| row.len());
+    if depth == 0 || width == 0 {
+        return Err(ReactorGridError::Empty);
+    }
+    if let Some(row) = columns.iter().position(|row| row.len() != width) {
+        return Err(ReactorGridError::UnevenRow { row: row + 1 });
+    }
+    let (x, z) = (width as i32 + 2, depth as i32 + 2);
+    if !(3..=18).contains(&x) || !(3..=18).contains(&z) || !(4..=18).contains(&y) {
+        return Err(ReactorGridError::OutOfRange { x, z, y });
+    }
+    // Inside is y - 2 tall and the control rod takes one of those
+    let max_height = y - 3;
+    let mut fuel_layout = Vec::with_capacity(width * depth);
+    for (row_z, row) in columns.iter().enumerate() {
+        for (column_x, height) in row.iter().enumerate() {
+            if *height < 0 || max_height < *height {
+                return Err(ReactorGridError::TooTall { x: column_x, z: row_z, height: *height, max: max_height });
+            }
+            fuel_layout.push(*height);
+        }
+    }
+    let fuel_assemblies: i32 = fuel_layout.iter().sum();
+    let mut reactor = FissionReactor {
+        x,
+        z,
+        y,
+        fuel_assemblies,
+        control_rods: fuel_layout.iter().filter(|height| **height > 0).count() as i32,
+        water_burn_rate: fuel_assemblies * FUEL_ASSEMBLY_FLUID_BURN_RATE,
+        heat_capacity: heat_capacity(x, z, y),
+        max_burn_rate: fuel_assemblies,
+        fuel_layout,
+        ..Default::default()
+    };
+    reactor.fuel_surface_area = fuel_surface_area(&reactor);
+    reactor.boil_efficiency = boil_efficiency(&reactor);
+    Ok(reactor)
+}
+
+/// Read a column map, one line per row of whitespace separated fuel stack heights
+pub fn parse_column_map(text: &str) -> Result<Vec<Vec<i32>>, ReactorGridError> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split_whitespace()
+                .map(|value| value.parse::<i32>().map_err(|_| ReactorGridError::NotANumber { value: value.to_string() }))
+                .collect()
+        })
+        .collect()
+}
+
+/// Column map of a reactor's fuel stack heights, the same format `parse_column_map` reads
+#[allow(dead_code)]
+pub fn column_map(reactor: &FissionReactor) -> String {
+    let width = (reactor.x - 2).max(1) as usize;
+    let mut map = String::new();
+    for row in fuel_columns(reactor).chunks(width) {
+        let heights: Vec<String> = row.iter().map(|height| height.to_string()).collect();
+        map.push_str(&heights.join(" "));
+        map.push('\n');
+    }
+    map
+}
+
+/// Build a reactor from ASCII layers of its inside, bottom layer first with a blank line between layers.
+/// F is a fuel assembly, K a control rod and . air, every fuel stack needs a control rod directly on top.
+pub fn reactor_from_layers(text: &str) -> Result<FissionReactor, ReactorGridError> {
+    let mut layers: Vec<Vec<Vec<char>>> = Vec::new();
+    let mut layer: Vec<Vec<char>> = Vec::new();
+    for line in text.lines().map(|line| line.trim()) {
+        if line.is_empty() {
+            if !layer.is_empty() {
+                layers.push(std::mem::take(&mut layer));
+            }
+        } else {
+            layer.push(line.chars().collect());
+        }
+    }
+    if !layer.is_empty() {
+        layers.push(layer);
+    }
+    let Some(first) = layers.first() else {
+        return Err(ReactorGridError::Empty);
+    };
+    let depth = first.len();
+    let width = first[0].len();
+    for (index, layer) in layers.iter().enumerate() {
+        if layer.len() != depth || layer.iter().any(|row| row.len() != width) {
+            return Err(ReactorGridError::UnevenGrid { layer: index + 1 });
+        }
+    }
+    let mut columns = vec![vec![0; width]; depth];
+    for (z, row) in columns.iter_mut().enumerate() {
+        for (x, height) in row.iter_mut().enumerate() {
+            // Bottom up, the only thing allowed is air, a stack of fuel, one control rod, then air
+            let column: String = layers.iter().map(|layer| layer[z][x]).collect();
+            if let Some(symbol) = column.chars().find(|symbol| !"FK.".contains(*symbol)) {
+                return Err(ReactorGridError::UnknownBlock { symbol });
+            }
+            let stack = column.trim_matches('.');
+            let fuel = stack.trim_end_matches('K');
+            if stack.is_empty() {
+                continue;
+            } else if fuel.is_empty() {
+                return Err(ReactorGridError::ControlRodWithoutFuel { x, z });
+            } else if fuel.contains(['K', '.']) || stack.len() - fuel.len() > 1 {
+                return Err(ReactorGridError::SplitColumn { x, z });
+            } else if fuel.len() == stack.len() {
+                return Err(ReactorGridError::FuelWithoutControlRod { x, z });
+            }
+            *height = fuel.len() as i32;
+        }
+    }
+    reactor_from_columns(&columns, layers.len() as i32 + 2)
+}
+
 /// area inside reactor, 
 /// assumes reactor has 100 efficency by spacing rods 1 block apart
 #[allow(dead_code)]
@@ -184,8 +583,8 @@ fn area_inside_reactor(x: i32, z: i32, y: i32) -> i32 {
 }
 
 fn fuel_assemblies_dimensions(x: i32, z: i32, y: i32) -> (i32, i32) {
-    // Area of x and z divided by 2 to keep ideal boil rate at 100%
-    // TODO This doesn't account for rounding, like an area of 9
+    // Area of x and z divided by 2 to keep ideal boil rate at 100%, rounded up like a checkerboard starting in a corner.
+    // fuel_placement::optimize_fuel_placement searches layouts that fit more.
     let f_x = x as f32;
     let f_z = z as f32;
     
@@ -215,24 +614,6 @@ fn heat_capacity(x: i32, z: i32, y: i32) -> i32 {
     (top_bottom + front_back + left_right) * CASING_HEAT_CAPACITY
 }
 
-// Find structure surface area https://github.com/mekanism/Mekanism/blob/1.20.4/src/generators/java/mekanism/generators/common/content/fission/FissionReactorValidator.java#L58
-
-// https://github.com/mekanism/Mekanism/blob/a3660901504ef724366224012bcea14be2cb734a/src/generators/java/mekanism/generators/common/content/fission/FissionReactorMultiblockData.java#L471
-// fn boil_efficiency(reactor: &FissionReactor) -> f32 {
-//     if reactor.fuel_assemblies == 0 {
-//         return 0.0;
-//     }
-//     let avg_surface_area = structure_surface_area(reactor.fuel_assemblies) / reactor.fuel_assemblies;
-//     0.0
-// }
-
-// fn structure_surface_area(fuel_assemblies: i32) -> i32 {
-//     let surface_area = fuel_assemblies * 6;  // Fuel Assemblies have six sides
-//     let factors: Vec<i64> = factor::factor(fuel_assemblies as i64);
-//     println!("{:?}",factors);
-//     0
-// }
-
 fn optimal_structure(fuel_assemblies: i32) -> (i32, i32, i32) {
     let factors: Vec<i64> = factor::factor(fuel_assemblies as i64);
     // Get middle pair, by finding the middle of the list in this case 3,4
@@ -268,12 +649,133 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
-    // #[test]
-    // fn test_structure_surface_area(){
-    //     let actual = 60;
-    //     let expected = structure_surface_area(12);
-    //     assert_eq!(actual, expected);
-    // }
+    #[test]
+    fn test_fuel_surface_area() {
+        // 6 lone stacks of 2
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        assert_eq!(fuel_surface_area(&reactor), 60);
+        assert_eq!(boil_efficiency(&reactor), 1.0);
+        // 12 lone stacks of 4 and one of 3
+        let reactor = utils::get_optimal_reactor(7,7,7);
+        assert_eq!(fuel_surface_area(&reactor), 230);
+        // Stacks of 2 packed 2x2 touch on every inner side, leaving 3 exposed faces per assembly
+        let reactor = reactor_from_columns(&parse_column_map("2 2\n2 2").unwrap(), 5).unwrap();
+        assert_eq!(reactor.fuel_surface_area, 24);
+        assert_eq!(reactor.boil_efficiency, 0.75);
+        // Stacks of different heights only touch up to the shorter one
+        let reactor = reactor_from_columns(&parse_column_map("3 1").unwrap(), 6).unwrap();
+        assert_eq!(reactor.fuel_surface_area, 14 + 6 - 2);
+    }
+
+    #[test]
+    fn test_fuel_columns() {
+        // 12 assemblies in 6 stacks of 2
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let actual = fuel_columns(&reactor);
+        assert_eq!(actual, vec![2, 0, 2, 0, 2, 0, 2, 0, 2, 0, 2, 0]);
+        // 51 assemblies, 12 stacks of 4 and one of 3
+        let reactor = utils::get_optimal_reactor(7,7,7);
+        let actual = fuel_columns(&reactor);
+        assert_eq!(actual.iter().sum::<i32>(), 51);
+        assert_eq!(actual.iter().filter(|h| **h == 4).count(), 12);
+        assert_eq!(actual.iter().filter(|h| **h == 3).count(), 1);
+    }
+
+    #[test]
+    fn test_reactor_from_columns() {
+        // Same stacks as the optimal 5x6x5
+        let columns = parse_column_map("2 0 2\n0 2 0\n2 0 2\n0 2 0").unwrap();
+        let actual = reactor_from_columns(&columns, 5).unwrap();
+        let expected = utils::get_optimal_reactor(5,6,5);
+        assert_eq!((actual.x, actual.z, actual.y), (expected.x, expected.z, expected.y));
+        assert_eq!(actual.fuel_assemblies, 12);
+        assert_eq!(actual.control_rods, 6);
+        assert_eq!(actual.water_burn_rate, expected.water_burn_rate);
+        assert_eq!(actual.heat_capacity, expected.heat_capacity);
+        assert_eq!(actual.max_burn_rate, 12);
+        assert_eq!(fuel_columns(&actual), vec![2, 0, 2, 0, 2, 0, 2, 0, 2, 0, 2, 0]);
+        // 3 fuel and a control rod don't fit in a 5 tall reactor
+        let columns = parse_column_map("3 0 0").unwrap();
+        assert_eq!(reactor_from_columns(&columns, 5), Err(ReactorGridError::TooTall { x: 0, z: 0, height: 3, max: 2 }));
+        assert_eq!(parse_column_map("1 a"), Err(ReactorGridError::NotANumber { value: "a".to_string() }));
+        let columns = parse_column_map("2 0 2\n0 2\n2 0 2").unwrap();
+        // Rows count from 1 like layers
+        assert_eq!(reactor_from_columns(&columns, 5), Err(ReactorGridError::UnevenRow { row: 2 }));
+        assert_eq!(ReactorGridError::UnevenRow { row: 2 }.to_string(), "Row 2 doesn't have as many columns as the first row.");
+    }
+
+    #[test]
+    fn test_reactor_from_layers() {
+        let layers = "F.F\n.F.\n\nF.F\n.F.\n\nK.K\n.K.";
+        let actual = reactor_from_layers(layers).unwrap();
+        assert_eq!((actual.x, actual.z, actual.y), (5, 4, 5));
+        assert_eq!(actual.fuel_assemblies, 6);
+        assert_eq!(actual.control_rods, 3);
+        assert_eq!(actual.fuel_layout, vec![2, 0, 2, 0, 2, 0]);
+        // Fuel on top with no control rod
+        let layers = "F..\n\nK..\n\n..F";
+        assert_eq!(reactor_from_layers(layers), Err(ReactorGridError::FuelWithoutControlRod { x: 2, z: 0 }));
+        // Control rod in the middle of a stack
+        let layers = "F\n\nK\n\nF\n\nK";
+        assert_eq!(reactor_from_layers(layers), Err(ReactorGridError::SplitColumn { x: 0, z: 0 }));
+        assert_eq!(reactor_from_layers("K"), Err(ReactorGridError::ControlRodWithoutFuel { x: 0, z: 0 }));
+        assert_eq!(reactor_from_layers("FX\n\nKK"), Err(ReactorGridError::UnknownBlock { symbol: 'X' }));
+        assert_eq!(reactor_from_layers("F.\n\nK"), Err(ReactorGridError::UnevenGrid { layer: 2 }));
+    }
+
+    #[test]
+    fn test_sodium_coolant() {
+        let mut reactor = utils::get_optimal_reactor(5,6,5);
+        assert_eq!(reactor.coolant, Coolant::Water);
+        assert_eq!(reactor.coolant_flow(), reactor.water_burn_rate);
+        // Ten times the water, sodium takes half the heat per mB and water only gets a fifth of it as steam
+        reactor.coolant = Coolant::Sodium;
+        assert_eq!(reactor.coolant_flow(), 2400000);
+        assert_eq!(reactor.heat_output(), 12000000.0);
+        assert_ne!(reactor, utils::get_optimal_reactor(5,6,5));
+    }
+
+    #[test]
+    fn test_sustainable_burn_rate() {
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        // 128,000 mB/t of water takes 6.4 MJ/t, the air another 5,127 J/t
+        assert_eq!(sustainable_burn_rate(&reactor, f64::MAX, PipeTier::Ultimate), 6.40);
+        assert_eq!(sustainable_burn_rate(&reactor, f64::MAX, PipeTier::Basic), 0.10);
+        // Less coming in than the pipe carries
+        assert_eq!(sustainable_burn_rate(&reactor, 100000.0, PipeTier::Ultimate), 5.00);
+        // Never more than the fuel assemblies burn
+        let mut reactor = reactor;
+        reactor.heat_capacity *= 10000;
+        assert_eq!(sustainable_burn_rate(&reactor, f64::MAX, PipeTier::Ultimate), reactor.max_burn_rate as f64);
+        // A 1,024,000 mB/t tube of sodium takes 5.12 MJ/t
+        let reactor = FissionReactor { coolant: Coolant::Sodium, ..utils::get_optimal_reactor(5,6,5) };
+        assert_eq!(sustainable_burn_rate(&reactor, f64::MAX, PipeTier::Ultimate), 5.12);
+    }
+
+    #[test]
+    fn test_sustainable_burn_rate_air_cooled() {
+        // Mekanism loses (T - 300 K) / (10,000 air + 10,000 casing insulation + 10 casing conduction) of the heat capacity.
+        // An 18x18x18 casing is 1,736 blocks of 1,000 J/K, with no coolant at 1,200 K the air takes
+        // 900 / 20,010 x 1,736,000 = 78,081 J/t, 0.078 mB/t of fuel, set in game as 0.07 mB/t
+        let reactor = FissionReactor { x: 18, z: 18, y: 18, heat_capacity: heat_capacity(18, 18, 18), max_burn_rate: 1000, ..Default::default() };
+        assert_eq!(reactor.heat_capacity, 1736000);
+        assert_eq!(sustainable_burn_rate(&reactor, 0.0, PipeTier::Ultimate), 0.07);
+    }
+
+    #[test]
+    fn test_fuel_usage() {
+        // 12 mB/t is 240 mB/s and 864,000 mB an hour out of a 96,000 mB tank
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let actual = fuel_usage(&reactor, 12.0);
+        assert_eq!(actual.fuel_per_second, 240.0);
+        assert_eq!(actual.fuel_per_hour, 864000.0);
+        assert_eq!(actual.waste_per_hour, actual.fuel_per_hour);
+        assert_eq!(actual.fuel_tank, 96000);
+        assert_eq!(actual.stall_seconds(), 400.0);
+        // Clamped to the max burn rate, and idle never stalls
+        assert_eq!(fuel_usage(&reactor, 100.0), actual);
+        assert_eq!(fuel_usage(&reactor, 0.0).stall_seconds(), f64::INFINITY);
+    }
 
     #[test]
     fn test_heat_capacity() {
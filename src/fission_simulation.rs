@@ -0,0 +1,234 @@
+// Tick by tick heat model of a fission reactor, for burn rate changes and losing coolant before trying them in game
+use crate::fission::{
+    FissionReactor, AMBIENT_TEMPERATURE, BASE_BOIL_TEMPERATURE, ENERGY_PER_FISSION_FUEL, ENVIRONMENT_INVERSE_CONDUCTION,
+    MIN_DAMAGE_TEMPERATURE,
+};
+
+const COOLANT_PER_VOLUME: f64 = 100000.0; // mB of water or sodium tank per block of the reactor
+const HEATED_COOLANT_PER_VOLUME: f64 = 1000000.0; // mB of steam or superheated sodium tank per block of the reactor
+/// Damage stops speeding up past this
+pub const MAX_DAMAGE_TEMPERATURE: f64 = 1800.0; // K
+/// Damage (%) at which a reactor still above MIN_DAMAGE_TEMPERATURE melts down
+pub const MAX_DAMAGE: f64 = 100.0;
+
+/// A rate that can change while simulating, like the burn rate or coolant coming in
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateProfile {
+    Constant(f64),
+    /// (starting tick, rate) pairs in tick order, each rate holds until the next step
+    Stepped(Vec<(u32, f64)>),
+}
+
+impl RateProfile {
+    pub fn rate_at(&self, tick: u32) -> f64 {
+        match self {
+            RateProfile::Constant(rate) => *rate,
+            RateProfile::Stepped(steps) => steps
+                .iter()
+                .take_while(|(start, _)| *start <= tick)
+                .last()
+                .map_or(0.0, |(_, rate)| *rate),
+        }
+    }
+}
+
+/// State of the reactor at the end of a tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct FissionTick {
+    pub tick: u32,
+    pub burn_rate: f64,  // mB/t
+    pub coolant_input: f64,  // mB/t
+    pub temperature: f64,  // K
+    pub heat_added: f64,  // J/t
+    pub heat_to_coolant: f64,  // J/t
+    pub environment_loss: f64,  // J/t
+    pub heated_coolant: f64,  // mB/t of steam or superheated sodium
+    pub stored_coolant: f64,  // mB
+    pub stored_heated_coolant: f64,  // mB
+    pub damage: f64,  // %
+}
+
+pub struct FissionSimulation {
+    pub ticks: Vec<FissionTick>,
+    /// Tick the reactor melted down on, the simulation stops there
+    pub meltdown_tick: Option<u32>,
+}
+
+impl FissionSimulation {
+    pub fn peak_temperature(&self) -> f64 {
+        self.ticks.iter().map(|t| t.temperature).fold(AMBIENT_TEMPERATURE, f64::max)
+    }
+
+    /// First tick the reactor took damage
+    pub fn first_damage_tick(&self) -> Option<u32> {
+        self.ticks.iter().find(|t| t.damage > 0.0).map(|t| t.tick)
+    }
+
+    pub fn print(&self) {
+        let Some(last) = self.ticks.last() else {
+            println!("Nothing was simulated.");
+            return;
+        };
+        println!("Simulated {} ticks", self.ticks.len());
+        println!("- Peak Temperature {:.2} K, Final Temperature {:.2} K", self.peak_temperature(), last.temperature);
+        println!("- Final Heat Added {:.0} J/t, to Coolant {:.0} J/t, to Environment {:.0} J/t", last.heat_added, last.heat_to_coolant, last.environment_loss);
+        println!("- Final Heated Coolant {:.0} mB/t, Stored Coolant {:.0} mB, Stored Heated Coolant {:.0} mB", last.heated_coolant, last.stored_coolant, last.stored_heated_coolant);
+        match self.first_damage_tick() {
+            Some(tick) => println!("- Took damage from tick {}, {:.2}% at the end", tick, last.damage),
+            None => println!("- Never took damage"),
+        }
+        match self.meltdown_tick {
+            Some(tick) => println!("- Melted down on tick {} ({:.1} s)\n", tick, tick as f64 / 20.0),
+            None => println!("- No meltdown\n"),
+        }
+    }
+}
+
+/// Step the reactor through `ticks` ticks, starting cold with a full coolant tank.
+/// Every tick fuel burns into heat, the hot casing heats the coolant and loses heat to the air, then damage
+/// builds above MIN_DAMAGE_TEMPERATURE and `heated_coolant_drain` mB/t of steam or superheated sodium is pulled out.
+pub fn simulate_fission(
+    reactor: &FissionReactor,
+    burn_rate: &RateProfile,
+    coolant: &RateProfile,
+    heated_coolant_drain: f64,
+    ticks: u32,
+) -> FissionSimulation {
+    let heat_capacity = reactor.heat_capacity as f64;
+    if heat_capacity <= 0.0 {
+        return FissionSimulation { ticks: Vec::new(), meltdown_tick: None };
+    }
+    let volume = (reactor.x * reactor.z * reactor.y) as f64;
+    let coolant_capacity = volume * COOLANT_PER_VOLUME;
+    let heated_coolant_capacity = volume * HEATED_COOLANT_PER_VOLUME;
+    let boil_efficiency = reactor.boil_efficiency as f64;
+    let heat_per_coolant = reactor.coolant.heat_per_mb();
+
+    let mut temperature = AMBIENT_TEMPERATURE;
+    let mut stored_coolant = coolant_capacity;
+    let mut stored_heated_coolant = 0.0;
+    let mut damage = 0.0;
+    let mut meltdown_tick = None;
+    let mut history = Vec::with_capacity(ticks as usize);
+    for tick in 1..=ticks {
+        let coolant_input = coolant.rate_at(tick).max(0.0);
+        stored_coolant = (stored_coolant + coolant_input).min(coolant_capacity);
+
+        // Can't burn faster than the reactor's assemblies allow
+        let burn = burn_rate.rate_at(tick).clamp(0.0, reactor.max_burn_rate as f64);
+        let heat_added = burn * ENERGY_PER_FISSION_FUEL;
+
+        // Heat the casing can pass to the coolant, only what the coolant actually takes leaves
+        let coolant_heat = boil_efficiency * (temperature - BASE_BOIL_TEMPERATURE) * heat_capacity;
+        let heated_coolant = (coolant_heat / heat_per_coolant)
+            .floor()
+            .min(stored_coolant)
+            .min(heated_coolant_capacity - stored_heated_coolant)
+            .max(0.0);
+        let heat_to_coolant = heated_coolant * heat_per_coolant;
+        stored_coolant -= heated_coolant;
+        stored_heated_coolant += heated_coolant;
+
+        let environment_loss = (temperature - AMBIENT_TEMPERATURE) / ENVIRONMENT_INVERSE_CONDUCTION * heat_capacity;
+        temperature += (heat_added - heat_to_coolant - environment_loss) / heat_capacity;
+
+        if temperature > MIN_DAMAGE_TEMPERATURE {
+            damage += temperature.min(MAX_DAMAGE_TEMPERATURE) / (MIN_DAMAGE_TEMPERATURE * 10.0);
+        } else {
+            damage = (damage - (MIN_DAMAGE_TEMPERATURE - temperature) / (MIN_DAMAGE_TEMPERATURE * 100.0)).max(0.0);
+        }
+        stored_heated_coolant -= stored_heated_coolant.min(heated_coolant_drain);
+
+        history.push(FissionTick {
+            tick,
+            burn_rate: burn,
+            coolant_input,
+            temperature,
+            heat_added,
+            heat_to_coolant,
+            environment_loss,
+            heated_coolant,
+            stored_coolant,
+            stored_heated_coolant,
+            damage,
+        });
+        if damage >= MAX_DAMAGE && temperature >= MIN_DAMAGE_TEMPERATURE {
+            meltdown_tick = Some(tick);
+            break;
+        }
+    }
+    FissionSimulation { ticks: history, meltdown_tick }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fission::{self, Coolant, PipeTier};
+    use crate::utils;
+
+    #[test]
+    fn test_rate_at() {
+        let profile = RateProfile::Stepped(vec![(10, 5.0), (20, 0.0)]);
+        assert_eq!(profile.rate_at(1), 0.0);
+        assert_eq!(profile.rate_at(15), 5.0);
+        assert_eq!(profile.rate_at(20), 0.0);
+        assert_eq!(RateProfile::Constant(2.5).rate_at(7), 2.5);
+    }
+
+    #[test]
+    fn test_simulate_fission_steady() {
+        // Fed its water burn rate, the reactor settles where the water takes all the heat
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let burn_rate = RateProfile::Constant(reactor.max_burn_rate as f64);
+        let coolant = RateProfile::Constant(reactor.water_burn_rate as f64);
+        let actual = simulate_fission(&reactor, &burn_rate, &coolant, f64::MAX, 2000);
+        assert_eq!(actual.meltdown_tick, None);
+        assert_eq!(actual.first_damage_tick(), None);
+        let last = actual.ticks.last().unwrap();
+        assert!((last.heated_coolant - reactor.water_burn_rate as f64).abs() / (reactor.water_burn_rate as f64) < 0.01);
+        assert!(last.temperature > BASE_BOIL_TEMPERATURE && last.temperature < MIN_DAMAGE_TEMPERATURE);
+        // Burning more than the assemblies allow is clamped to the max burn rate
+        let actual = simulate_fission(&reactor, &RateProfile::Constant(1000.0), &coolant, f64::MAX, 1);
+        assert_eq!(actual.ticks[0].burn_rate, reactor.max_burn_rate as f64);
+        // Sodium cooled, it settles at the same temperature heating ten times as much sodium
+        let water_cooled = simulate_fission(&reactor, &burn_rate, &coolant, f64::MAX, 2000);
+        let mut reactor = reactor;
+        reactor.coolant = Coolant::Sodium;
+        let coolant = RateProfile::Constant(reactor.coolant_flow() as f64);
+        let actual = simulate_fission(&reactor, &burn_rate, &coolant, f64::MAX, 2000);
+        let last = actual.ticks.last().unwrap();
+        assert!((last.heated_coolant - reactor.coolant_flow() as f64).abs() / (reactor.coolant_flow() as f64) < 0.01);
+        assert!((last.temperature - water_cooled.ticks.last().unwrap().temperature).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_sustainable_burn_rate_holds() {
+        // Fed one basic pipe of water, the sustainable rate stays under damage and a bit more doesn't
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let sustainable = fission::sustainable_burn_rate(&reactor, f64::MAX, PipeTier::Basic);
+        let coolant = RateProfile::Constant(PipeTier::Basic.throughput(Coolant::Water));
+        let actual = simulate_fission(&reactor, &RateProfile::Constant(sustainable), &coolant, f64::MAX, 200000);
+        assert_eq!(actual.first_damage_tick(), None);
+        let actual = simulate_fission(&reactor, &RateProfile::Constant(sustainable + 0.05), &coolant, f64::MAX, 200000);
+        assert!(actual.first_damage_tick().is_some());
+    }
+
+    #[test]
+    fn test_simulate_fission_coolant_loss() {
+        // Water stops after 100 ticks, the tank runs dry and the reactor heats until it melts down
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let burn_rate = RateProfile::Constant(reactor.max_burn_rate as f64);
+        let coolant = RateProfile::Stepped(vec![(0, reactor.water_burn_rate as f64), (100, 0.0)]);
+        let actual = simulate_fission(&reactor, &burn_rate, &coolant, f64::MAX, 20000);
+        let damaged = actual.first_damage_tick().unwrap();
+        let meltdown = actual.meltdown_tick.unwrap();
+        assert!(100 < damaged && damaged < meltdown);
+        assert!(actual.peak_temperature() > MIN_DAMAGE_TEMPERATURE);
+        // Shutting down and bringing the water back once it took damage lets it cool and repair
+        let burn_rate = RateProfile::Stepped(vec![(0, reactor.max_burn_rate as f64), (damaged + 20, 0.0)]);
+        let coolant = RateProfile::Stepped(vec![(0, reactor.water_burn_rate as f64), (100, 0.0), (damaged + 20, reactor.water_burn_rate as f64)]);
+        let actual = simulate_fission(&reactor, &burn_rate, &coolant, f64::MAX, 20000);
+        assert_eq!(actual.meltdown_tick, None);
+        assert_eq!(actual.ticks.last().unwrap().damage, 0.0);
+    }
+}
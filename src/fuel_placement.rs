@@ -0,0 +1,222 @@
+// Search where fuel stacks go inside a reactor of set dimensions, to fit the most fuel assemblies at a boil efficiency
+use crate::fission::{self, FissionReactor, ReactorGridError};
+
+/// Which columns inside a reactor get a fuel stack
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// Every other column, no two stacks touch
+    Checkerboard,
+    /// Every other row filled end to end
+    Stripes,
+    /// Every column
+    Solid,
+    /// Nothing to start from, only stacks added one at a time
+    Greedy,
+    /// Tiled over the inside, rows are z and each row's entries x, true for a stack
+    Custom(Vec<Vec<bool>>),
+}
+
+impl Pattern {
+    pub const BUILT_IN: [Pattern; 4] = [Pattern::Checkerboard, Pattern::Stripes, Pattern::Solid, Pattern::Greedy];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Checkerboard => "Checkerboard",
+            Pattern::Stripes => "Stripes",
+            Pattern::Solid => "Solid",
+            Pattern::Greedy => "Greedy",
+            Pattern::Custom(_) => "Custom",
+        }
+    }
+
+    /// Columns with a stack, indexed `x + z * width` like `fission::fuel_columns`
+    pub fn mask(&self, width: usize, depth: usize) -> Vec<bool> {
+        let mut mask = Vec::with_capacity(width * depth);
+        for z in 0..depth {
+            for x in 0..width {
+                mask.push(match self {
+                    Pattern::Checkerboard => (x + z) % 2 == 0,
+                    Pattern::Stripes => z % 2 == 0,
+                    Pattern::Solid => true,
+                    Pattern::Greedy => false,
+                    Pattern::Custom(tile) => {
+                        let row = &tile[z % tile.len()];
+                        row[x % row.len()]
+                    }
+                });
+            }
+        }
+        mask
+    }
+}
+
+/// Read a custom pattern tile, F for a stack and . for an empty column, one line per row
+pub fn parse_pattern(text: &str) -> Result<Pattern, ReactorGridError> {
+    let mut tile: Vec<Vec<bool>> = Vec::new();
+    for line in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        let mut row = Vec::new();
+        for symbol in line.chars() {
+            match symbol {
+                'F' => row.push(true),
+                '.' => row.push(false),
+                _ => return Err(ReactorGridError::UnknownBlock { symbol }),
+            }
+        }
+        tile.push(row);
+    }
+    if tile.is_empty() {
+        return Err(ReactorGridError::Empty);
+    }
+    Ok(Pattern::Custom(tile))
+}
+
+/// Where the fuel went and the reactor it makes
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuelPlacement {
+    pub pattern: Pattern,
+    pub added_stacks: i32,  // Stacks added one at a time on top of the pattern
+    pub reactor: FissionReactor,
+}
+
+impl FuelPlacement {
+    pub fn print(&self) {
+        println!("{} pattern with {} added stacks", self.pattern.name(), self.added_stacks);
+        println!("Fuel stack height of each column:");
+        print!("{}", fission::column_map(&self.reactor));
+        self.reactor.print();
+    }
+}
+
+fn reactor_from_mask(mask: &[bool], width: usize, y: i32) -> Result<FissionReactor, ReactorGridError> {
+    // Full height stacks, one control rod on top
+    let height = y - 3;
+    let columns: Vec<Vec<i32>> = mask
+        .chunks(width)
+        .map(|row| row.iter().map(|stack| if *stack { height } else { 0 }).collect())
+        .collect();
+    fission::reactor_from_columns(&columns, y)
+}
+
+/// Start from a pattern, then keep adding the stack that touches the fewest others while boil efficiency holds
+fn fill_pattern(pattern: &Pattern, x: i32, z: i32, y: i32, min_efficiency: f32) -> Result<Option<FuelPlacement>, ReactorGridError> {
+    let (width, depth) = ((x - 2) as usize, (z - 2) as usize);
+    let mut mask = pattern.mask(width, depth);
+    let mut reactor = reactor_from_mask(&mask, width, y)?;
+    if reactor.fuel_assemblies > 0 && reactor.boil_efficiency < min_efficiency {
+        return Ok(None);
+    }
+    let mut added_stacks = 0;
+    loop {
+        let neighbours = |i: usize| {
+            let (column_x, column_z) = (i % width, i / width);
+            [
+                column_x > 0 && mask[i - 1],
+                column_x + 1 < width && mask[i + 1],
+                column_z > 0 && mask[i - width],
+                column_z + 1 < depth && mask[i + width],
+            ]
+            .iter()
+            .filter(|touching| **touching)
+            .count()
+        };
+        let mut empty: Vec<usize> = (0..mask.len()).filter(|i| !mask[*i]).collect();
+        empty.sort_by_key(|i| neighbours(*i));
+        let mut added = None;
+        for i in empty {
+            mask[i] = true;
+            let candidate = reactor_from_mask(&mask, width, y)?;
+            if candidate.boil_efficiency >= min_efficiency {
+                added = Some(candidate);
+                break;
+            }
+            mask[i] = false;
+        }
+        match added {
+            Some(candidate) => {
+                reactor = candidate;
+                added_stacks += 1;
+            }
+            None => break,
+        }
+    }
+    Ok(Some(FuelPlacement { pattern: pattern.clone(), added_stacks, reactor }))
+}
+
+/// Most fuel assemblies for a reactor's dimensions, keeping boil efficiency at or above `min_efficiency`.
+/// Every built in pattern and the custom ones are topped up stack by stack, then the most fuel wins,
+/// ties going to the higher boil efficiency.
+pub fn optimize_fuel_placement(x: i32, z: i32, y: i32, min_efficiency: f32, custom: &[Pattern]) -> Result<FuelPlacement, ReactorGridError> {
+    // Checked before the inside's width and depth are worked out from them
+    if !(3..=18).contains(&x) || !(3..=18).contains(&z) || !(4..=18).contains(&y) {
+        return Err(ReactorGridError::OutOfRange { x, z, y });
+    }
+    let mut best: Option<FuelPlacement> = None;
+    for pattern in Pattern::BUILT_IN.iter().chain(custom) {
+        let Some(placement) = fill_pattern(pattern, x, z, y, min_efficiency)? else {
+            continue;
+        };
+        let better = match &best {
+            None => true,
+            Some(best) => (placement.reactor.fuel_assemblies, placement.reactor.boil_efficiency)
+                > (best.reactor.fuel_assemblies, best.reactor.boil_efficiency),
+        };
+        if better {
+            best = Some(placement);
+        }
+    }
+    // Greedy always fits at least one stack, so something is found whenever the dimensions are valid
+    best.ok_or(ReactorGridError::Empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn test_optimize_fuel_placement() {
+        // Stacks of 2 can touch a few others and still keep 4 faces per assembly, so the checkerboard's two empty corners fill up
+        let expected = utils::get_optimal_reactor(5,6,5);
+        let actual = optimize_fuel_placement(5, 6, 5, 1.0, &[]).unwrap();
+        assert_eq!(actual.reactor.boil_efficiency, 1.0);
+        assert_eq!(actual.reactor.fuel_assemblies, expected.fuel_assemblies + 4);
+        // An odd inside of 3x3 fits 5 stacks in a checkerboard, then one more at 100%
+        let actual = optimize_fuel_placement(5, 5, 5, 1.0, &[]).unwrap();
+        assert_eq!(actual.pattern, Pattern::Checkerboard);
+        assert_eq!(actual.added_stacks, 1);
+        assert_eq!(actual.reactor.fuel_assemblies, 12);
+        assert_eq!(actual.reactor.control_rods, 6);
+    }
+
+    #[test]
+    fn test_optimize_fuel_placement_trade_efficiency() {
+        let full = optimize_fuel_placement(7, 7, 7, 1.0, &[]).unwrap();
+        let traded = optimize_fuel_placement(7, 7, 7, 0.75, &[]).unwrap();
+        assert!(traded.reactor.fuel_assemblies > full.reactor.fuel_assemblies);
+        assert!(traded.reactor.boil_efficiency >= 0.75);
+        // Nothing beats every column once efficiency doesn't matter
+        let solid = optimize_fuel_placement(7, 7, 7, 0.0, &[]).unwrap();
+        assert_eq!(solid.reactor.fuel_assemblies, 5 * 5 * 4);
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let pattern = parse_pattern("F.\n..").unwrap();
+        assert_eq!(pattern.mask(3, 3), vec![true, false, true, false, false, false, true, false, true]);
+        // Custom patterns are tried alongside the built in ones
+        let actual = optimize_fuel_placement(5, 5, 5, 1.0, &[pattern]).unwrap();
+        assert_eq!(actual.reactor.fuel_assemblies, 12);
+        assert_eq!(parse_pattern("FX"), Err(ReactorGridError::UnknownBlock { symbol: 'X' }));
+    }
+
+    #[test]
+    fn test_optimize_fuel_placement_out_of_range() {
+        // Too small to have an inside, or too big for a multiblock
+        assert_eq!(optimize_fuel_placement(2, 5, 5, 1.0, &[]), Err(ReactorGridError::OutOfRange { x: 2, z: 5, y: 5 }));
+        assert_eq!(optimize_fuel_placement(5, 1, 5, 1.0, &[]), Err(ReactorGridError::OutOfRange { x: 5, z: 1, y: 5 }));
+        assert_eq!(optimize_fuel_placement(30, 5, 5, 1.0, &[]), Err(ReactorGridError::OutOfRange { x: 30, z: 5, y: 5 }));
+        // Smallest reactor, one stack of one
+        let actual = optimize_fuel_placement(3, 3, 4, 0.0, &[]).unwrap();
+        assert_eq!(actual.reactor.fuel_assemblies, 1);
+    }
+}
@@ -0,0 +1,398 @@
+// Voxel models of multiblocks, where every block goes.  Used for recipes, build guides and schematics
+use std::collections::VecDeque;
+
+use crate::boiler::Boiler;
+use crate::fission::{self, FissionReactor};
+use crate::turbine::Turbine;
+
+/// Turbine valves placed on the rotor section, one for steam in and one for energy out
+const TURBINE_VALVES: i32 = 2;
+/// Fission reactor ports, for coolant in, heated coolant out, fuel in and waste out
+const REACTOR_PORTS: i32 = 4;
+/// Boiler valves, for water in and steam out
+const BOILER_VALVES: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Block {
+    Air,
+    TurbineCasing,
+    StructuralGlass,
+    TurbineValve,
+    TurbineVent,
+    PressureDisperser,
+    TurbineRotor,
+    RotationalComplex,
+    ElectromagneticCoil,
+    SaturatingCondenser,
+    FissionReactorCasing,
+    FissionReactorPort,
+    FissionFuelAssembly,
+    ControlRodAssembly,
+    BoilerCasing,
+    BoilerValve,
+    SuperheatingElement,
+}
+
+impl Block {
+    /// Every block that can be placed, in the order recipes list them
+    pub const ALL: [Block; 16] = [
+        Block::TurbineCasing,
+        Block::StructuralGlass,
+        Block::TurbineValve,
+        Block::TurbineVent,
+        Block::PressureDisperser,
+        Block::TurbineRotor,
+        Block::RotationalComplex,
+        Block::ElectromagneticCoil,
+        Block::SaturatingCondenser,
+        Block::FissionReactorCasing,
+        Block::FissionReactorPort,
+        Block::FissionFuelAssembly,
+        Block::ControlRodAssembly,
+        Block::BoilerCasing,
+        Block::BoilerValve,
+        Block::SuperheatingElement,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Block::Air => "Air",
+            Block::TurbineCasing => "Turbine Casing",
+            Block::StructuralGlass => "Structural Glass",
+            Block::TurbineValve => "Turbine Valve",
+            Block::TurbineVent => "Turbine Vent",
+            Block::PressureDisperser => "Pressure Disperser",
+            Block::TurbineRotor => "Turbine Rotor",
+            Block::RotationalComplex => "Rotational Complex",
+            Block::ElectromagneticCoil => "Electromagnetic Coil",
+            Block::SaturatingCondenser => "Saturating Condenser",
+            Block::FissionReactorCasing => "Fission Reactor Casing",
+            Block::FissionReactorPort => "Fission Reactor Port",
+            Block::FissionFuelAssembly => "Fission Fuel Assembly",
+            Block::ControlRodAssembly => "Control Rod Assembly",
+            Block::BoilerCasing => "Boiler Casing",
+            Block::BoilerValve => "Boiler Valve",
+            Block::SuperheatingElement => "Superheating Element",
+        }
+    }
+
+    /// Character used for the block in build guides
+    pub fn symbol(&self) -> char {
+        match self {
+            Block::Air => '.',
+            Block::TurbineCasing | Block::FissionReactorCasing | Block::BoilerCasing => 'C',
+            Block::StructuralGlass => 'G',
+            Block::TurbineValve | Block::FissionReactorPort | Block::BoilerValve => 'P',
+            Block::TurbineVent => 'V',
+            Block::PressureDisperser => 'D',
+            Block::TurbineRotor => 'R',
+            Block::RotationalComplex => 'X',
+            Block::ElectromagneticCoil => 'E',
+            Block::SaturatingCondenser => 'S',
+            Block::FissionFuelAssembly => 'F',
+            Block::ControlRodAssembly => 'K',
+            Block::SuperheatingElement => 'H',
+        }
+    }
+}
+
+/// Blocks of a multiblock, indexed by x (length), y (height), z (width) starting at the bottom corner
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    blocks: Vec<Block>,
+}
+
+impl Layout {
+    /// Layout filled with air
+    pub fn new(x: i32, y: i32, z: i32) -> Layout {
+        Layout { x, y, z, blocks: vec![Block::Air; (x * y * z).max(0) as usize] }
+    }
+
+    fn index(&self, x: i32, y: i32, z: i32) -> usize {
+        ((y * self.z + z) * self.x + x) as usize
+    }
+
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Block {
+        self.blocks[self.index(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, z: i32, block: Block) {
+        let index = self.index(x, y, z);
+        self.blocks[index] = block;
+    }
+
+    /// Number of times a block is used
+    pub fn count(&self, block: Block) -> i32 {
+        self.blocks.iter().filter(|b| **b == block).count() as i32
+    }
+
+    /// Count of every block used, air left out
+    pub fn counts(&self) -> Vec<(Block, i32)> {
+        Block::ALL
+            .iter()
+            .map(|block| (*block, self.count(*block)))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    /// Is the position on the outside of the multiblock
+    pub fn is_shell(&self, x: i32, y: i32, z: i32) -> bool {
+        x == 0 || y == 0 || z == 0 || x == self.x - 1 || y == self.y - 1 || z == self.z - 1
+    }
+
+    /// Is the position on the frame, where two or more outside faces meet
+    pub fn is_frame(&self, x: i32, y: i32, z: i32) -> bool {
+        let on_x = x == 0 || x == self.x - 1;
+        let on_y = y == 0 || y == self.y - 1;
+        let on_z = z == 0 || z == self.z - 1;
+        (on_x as i32 + on_y as i32 + on_z as i32) >= 2
+    }
+}
+
+/// Build the block by block model of a turbine.
+/// Rotors run up the center, the disperser layer with the rotational complex sits on top of them,
+/// coils grow out from above the complex and condensers fill the rest of the upper section.
+/// Vents go on the top then down the sides of the upper section, glass and valves on the sides of the rotor section.
+/// Parts that don't fit are left out, use `turbine::validate_turbine` to check a turbine first.
+pub fn turbine_layout(turbine: &Turbine) -> Layout {
+    let size = turbine.x_z;
+    let height = turbine.y;
+    let mut layout = Layout::new(size, height, size);
+    let center = size / 2;
+    let disperser_y = turbine.shaft_height + 1;
+
+    // Shell, casing everywhere then glass on the sides of the rotor section
+    for y in 0..height {
+        for z in 0..size {
+            for x in 0..size {
+                if !layout.is_shell(x, y, z) {
+                    continue;
+                }
+                let block = if !layout.is_frame(x, y, z) && y >= 1 && y <= turbine.shaft_height {
+                    Block::StructuralGlass
+                } else {
+                    Block::TurbineCasing
+                };
+                layout.set(x, y, z, block);
+            }
+        }
+    }
+    // Valves on opposite sides of the bottom rotor level
+    let valve_spots = [(0, center), (size - 1, center)];
+    for (x, z) in valve_spots.iter().take(TURBINE_VALVES as usize) {
+        if height > 2 && size > 2 {
+            layout.set(*x, 1, *z, Block::TurbineValve);
+        }
+    }
+    // Vents, top face first then the sides from the top level down to the disperser layer
+    let mut vent_spots = Vec::new();
+    for z in 1..size - 1 {
+        for x in 1..size - 1 {
+            vent_spots.push((x, height - 1, z));
+        }
+    }
+    for y in (disperser_y..height - 1).rev() {
+        for i in 1..size - 1 {
+            vent_spots.push((i, y, 0));
+            vent_spots.push((i, y, size - 1));
+            vent_spots.push((0, y, i));
+            vent_spots.push((size - 1, y, i));
+        }
+    }
+    for (x, y, z) in vent_spots.into_iter().take(turbine.vents.max(0) as usize) {
+        layout.set(x, y, z, Block::TurbineVent);
+    }
+    // Rotor shaft
+    for y in 1..=turbine.shaft_height.min(height - 2) {
+        layout.set(center, y, center, Block::TurbineRotor);
+    }
+    // Disperser layer
+    if disperser_y < height - 1 {
+        for z in 1..size - 1 {
+            for x in 1..size - 1 {
+                layout.set(x, disperser_y, z, Block::PressureDisperser);
+            }
+        }
+        layout.set(center, disperser_y, center, Block::RotationalComplex);
+    }
+    // Coils have to touch the rotational complex or another coil, so grow them outwards from above it
+    let start = (center, disperser_y + 1, center);
+    let in_upper = |(x, y, z): (i32, i32, i32)| {
+        x >= 1 && x < size - 1 && z >= 1 && z < size - 1 && y > disperser_y && y < height - 1
+    };
+    let mut coils_left = turbine.coils;
+    let mut queue = VecDeque::new();
+    let mut seen = vec![start];
+    if in_upper(start) {
+        queue.push_back(start);
+    }
+    while let Some((x, y, z)) = queue.pop_front() {
+        if coils_left <= 0 {
+            break;
+        }
+        layout.set(x, y, z, Block::ElectromagneticCoil);
+        coils_left -= 1;
+        for next in [(x + 1, y, z), (x - 1, y, z), (x, y, z + 1), (x, y, z - 1), (x, y + 1, z)] {
+            if in_upper(next) && !seen.contains(&next) {
+                seen.push(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    // Condensers anywhere left in the upper section, bottom up
+    let mut condensers_left = turbine.condensers;
+    'outer: for y in disperser_y + 1..height - 1 {
+        for z in 1..size - 1 {
+            for x in 1..size - 1 {
+                if condensers_left <= 0 {
+                    break 'outer;
+                }
+                if layout.get(x, y, z) == Block::Air {
+                    layout.set(x, y, z, Block::SaturatingCondenser);
+                    condensers_left -= 1;
+                }
+            }
+        }
+    }
+    layout
+}
+
+/// Build the block by block model of a fission reactor.
+/// Fuel assembly stacks follow `fission::fuel_columns` with a control rod on top of each,
+/// ports go in the middle of each side on the bottom level.
+pub fn fission_layout(reactor: &FissionReactor) -> Layout {
+    let mut layout = Layout::new(reactor.x, reactor.y, reactor.z);
+    for y in 0..reactor.y {
+        for z in 0..reactor.z {
+            for x in 0..reactor.x {
+                if layout.is_shell(x, y, z) {
+                    layout.set(x, y, z, Block::FissionReactorCasing);
+                }
+            }
+        }
+    }
+    let port_spots = [
+        (0, reactor.z / 2),
+        (reactor.x - 1, reactor.z / 2),
+        (reactor.x / 2, 0),
+        (reactor.x / 2, reactor.z - 1),
+    ];
+    for (x, z) in port_spots.iter().take(REACTOR_PORTS as usize) {
+        if reactor.y > 2 && !layout.is_frame(*x, 1, *z) {
+            layout.set(*x, 1, *z, Block::FissionReactorPort);
+        }
+    }
+    let width = reactor.x - 2;
+    for (i, height) in fission::fuel_columns(reactor).iter().enumerate() {
+        if *height <= 0 {
+            continue;
+        }
+        let x = i as i32 % width + 1;
+        let z = i as i32 / width + 1;
+        for y in 1..=*height {
+            layout.set(x, y, z, Block::FissionFuelAssembly);
+        }
+        layout.set(x, height + 1, z, Block::ControlRodAssembly);
+    }
+    layout
+}
+
+/// Build the block by block model of a boiler.
+/// Superheating elements fill the bottom of the water cavity, the full disperser layer sits right above them
+/// with valves on the bottom level.
+pub fn boiler_layout(boiler: &Boiler) -> Layout {
+    let mut layout = Layout::new(boiler.x, boiler.y, boiler.z);
+    for y in 0..boiler.y {
+        for z in 0..boiler.z {
+            for x in 0..boiler.x {
+                if layout.is_shell(x, y, z) {
+                    layout.set(x, y, z, Block::BoilerCasing);
+                }
+            }
+        }
+    }
+    let valve_spots = [(0, boiler.z / 2), (boiler.x - 1, boiler.z / 2)];
+    for (x, z) in valve_spots.iter().take(BOILER_VALVES as usize) {
+        if boiler.y > 2 && !layout.is_frame(*x, 1, *z) {
+            layout.set(*x, 1, *z, Block::BoilerValve);
+        }
+    }
+    let area = (boiler.x - 2) * (boiler.z - 2);
+    if area <= 0 {
+        return layout;
+    }
+    let mut elements_left = boiler.heating_element;
+    'outer: for y in 1..boiler.y - 1 {
+        for z in 1..boiler.z - 1 {
+            for x in 1..boiler.x - 1 {
+                if elements_left <= 0 {
+                    break 'outer;
+                }
+                layout.set(x, y, z, Block::SuperheatingElement);
+                elements_left -= 1;
+            }
+        }
+    }
+    let disperser_y = boiler.disperser_layer();
+    if boiler.dispersers > 0 && disperser_y < boiler.y - 1 {
+        for z in 1..boiler.z - 1 {
+            for x in 1..boiler.x - 1 {
+                layout.set(x, disperser_y, z, Block::PressureDisperser);
+            }
+        }
+    }
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn test_turbine_layout() {
+        for (x_z, y) in [(5, 5), (5, 9), (7, 13), (9, 17), (17, 18)] {
+            let turbine = utils::get_optimal_turbine(x_z, y);
+            let layout = turbine_layout(&turbine);
+            assert_eq!(layout.count(Block::TurbineVent), turbine.vents);
+            assert_eq!(layout.count(Block::PressureDisperser), turbine.dispersers);
+            assert_eq!(layout.count(Block::TurbineRotor), turbine.shaft_height);
+            assert_eq!(layout.count(Block::RotationalComplex), 1);
+            assert_eq!(layout.count(Block::ElectromagneticCoil), turbine.coils);
+            assert_eq!(layout.count(Block::SaturatingCondenser), turbine.condensers);
+            assert_eq!(layout.count(Block::TurbineValve), TURBINE_VALVES);
+            let placed: i32 = layout.counts().iter().map(|(_, count)| count).sum();
+            assert_eq!(placed, turbine.block_count());
+        }
+    }
+
+    #[test]
+    fn test_fission_layout() {
+        let reactor = utils::get_optimal_reactor(7,7,7);
+        let layout = fission_layout(&reactor);
+        assert_eq!(layout.count(Block::FissionFuelAssembly), reactor.fuel_assemblies);
+        assert_eq!(layout.count(Block::ControlRodAssembly), reactor.control_rods);
+        assert_eq!(layout.count(Block::FissionReactorPort), REACTOR_PORTS);
+        // 7x7x7 shell
+        assert_eq!(layout.count(Block::FissionReactorCasing) + REACTOR_PORTS, 7 * 7 * 7 - 5 * 5 * 5);
+        assert_eq!(layout.get(1, 4, 1), Block::FissionFuelAssembly);
+        assert_eq!(layout.get(1, 5, 1), Block::ControlRodAssembly);
+    }
+
+    #[test]
+    fn test_turbine_layout_placement() {
+        let turbine = utils::get_optimal_turbine(5,9);
+        let layout = turbine_layout(&turbine);
+        // Corners are casing, rotor section sides are glass
+        assert_eq!(layout.get(0, 0, 0), Block::TurbineCasing);
+        assert_eq!(layout.get(0, 2, 2), Block::StructuralGlass);
+        // Shaft, complex and the first coil are stacked in the center
+        assert_eq!(layout.get(2, 4, 2), Block::TurbineRotor);
+        assert_eq!(layout.get(2, 5, 2), Block::RotationalComplex);
+        assert_eq!(layout.get(2, 6, 2), Block::ElectromagneticCoil);
+        assert_eq!(layout.get(1, 8, 1), Block::TurbineVent);
+    }
+}
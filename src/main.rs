@@ -1,11 +1,19 @@
 #![allow(dead_code)]
 mod boiler;
+mod build_guide;
 mod fission;
+mod fission_simulation;
+mod fuel_placement;
+mod layout;
 mod metric_prefix;
 mod setups;
+mod steam;
 mod turbine;
+mod turbine_analysis;
+mod turbine_simulation;
 mod utils;
 mod recipe;
+mod schematic;
 mod lookup_table;
 
 use std::env;
@@ -32,6 +40,19 @@ fn read_user_input() -> String {
     user_input.trim().to_string()
 }
 
+/// Read lines from stdin until one is "done", keeping blank lines
+fn read_user_lines() -> String {
+    let mut lines = Vec::new();
+    loop {
+        let line = read_user_input();
+        if line.eq("done") {
+            break;
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
 /// Handle interactive "REPL" use of tool, Root level loop
 fn interactive() {
     println!("Welcome to Mekanism Ratio Calculator, interactive mode.");
@@ -41,6 +62,11 @@ fn interactive() {
                         l: List created setups\n\
                         p: print created setups\n\
                         r: Get all materials needed for select setup\n\
+                        s: Simulate the turbine of a created setup\n\
+                        h: Simulate the heat of a created setup's fission reactor\n\
+                        u: Fuel used, waste made and the chains around them for a created setup\n\
+                        e: Export a created setup as schematics\n\
+                        a: Analyze upgrades and bottlenecks for the turbines of a created setup\n\
                         q: Quit";
     let mut setups: Vec<setups::SetupType> = Vec::new();
     loop {
@@ -70,6 +96,14 @@ fn interactive() {
                 for setup in setups.iter() {
                     setup.print();
                 }
+                if !setups.is_empty() {
+                    println!("Show layer by layer build guides? (y/n)");
+                    if read_user_input().eq("y") {
+                        for setup in setups.iter() {
+                            setup.print_build_guide();
+                        }
+                    }
+                }
             },
             "r" => {
                 if setups.len() == 0 {
@@ -78,6 +112,123 @@ fn interactive() {
                 println!("Which created setup do you wish to get parts for?  Choose the index.");
                 let index = read_user_input().parse::<usize>().unwrap();
                 let setup = &setups[index];
+                setup.generate_recipe();
+            },
+            "s" => {
+                if setups.is_empty() {
+                    println!("No Setups created yet, try creating one first.");
+                    continue;
+                }
+                println!("Which created setup do you wish to simulate?  Choose the index.");
+                let index = read_user_input().parse::<usize>().unwrap();
+                let turbines = setups[index].turbines();
+                let turbine = if turbines.len() > 1 {
+                    println!("Which turbine of the setup? Choose the index 0 to {}.", turbines.len() - 1);
+                    &turbines[read_user_input().parse::<usize>().unwrap()]
+                } else {
+                    &turbines[0]
+                };
+                interactive_turbine_simulation(turbine);
+            },
+            "h" => {
+                if setups.is_empty() {
+                    println!("No Setups created yet, try creating one first.");
+                    continue;
+                }
+                println!("Which created setup do you wish to simulate?  Choose the index.");
+                let index = read_user_input().parse::<usize>().unwrap();
+                interactive_fission_simulation(setups[index].reactor());
+            },
+            "u" => {
+                if setups.is_empty() {
+                    println!("No Setups created yet, try creating one first.");
+                    continue;
+                }
+                println!("Which created setup do you wish to fuel?  Choose the index.");
+                let index = read_user_input().parse::<usize>().unwrap();
+                let reactor = setups[index].reactor();
+                println!("Input burn rate (mB/t). (blank for max burn rate)");
+                let burn_rate = read_user_input().parse::<f64>().unwrap_or(reactor.max_burn_rate as f64);
+                let usage = fission::fuel_usage(reactor, burn_rate);
+                usage.print();
+                println!("Energy per mB of fuel {}\n", metric_prefix::display_energy(setups[index].energy_per_fuel(usage.burn_rate)));
+                println!("Plan the fuel supply chain? (y/n)");
+                if read_user_input().eq("y") {
+                    println!("Enriching tier (m: machine, b: basic, a: advanced, e: elite, u: ultimate factory)");
+                    let tier = match read_user_input().as_ref() {
+                        "b" => recipe::FactoryTier::Basic,
+                        "a" => recipe::FactoryTier::Advanced,
+                        "e" => recipe::FactoryTier::Elite,
+                        "u" => recipe::FactoryTier::Ultimate,
+                        _ => recipe::FactoryTier::Machine,
+                    };
+                    println!("Input speed upgrades in each machine, 0 to 8.");
+                    let speed_upgrades = read_user_input().parse::<i32>().unwrap_or(0);
+                    recipe::fuel_supply_chain(usage.burn_rate, tier, speed_upgrades).print();
+                }
+                println!("Plan the waste processing? (y/n)");
+                if read_user_input().eq("y") {
+                    println!("Waste into (p: plutonium, o: polonium pellets, a: antimatter)");
+                    let route = match read_user_input().as_ref() {
+                        "o" => recipe::WasteRoute::PoloniumPellets,
+                        "a" => recipe::WasteRoute::Antimatter,
+                        _ => recipe::WasteRoute::Plutonium,
+                    };
+                    println!("Input speed upgrades in each machine, 0 to 8.");
+                    let speed_upgrades = read_user_input().parse::<i32>().unwrap_or(0);
+                    println!("Input share of the day the sun is out, 0 to 1. (blank for 0.5)");
+                    let day_ratio = read_user_input().parse::<f64>().unwrap_or(0.5);
+                    // One mB of waste per mB of fuel
+                    recipe::waste_processing(usage.burn_rate, route, speed_upgrades, day_ratio).print();
+                }
+            },
+            "e" => {
+                if setups.is_empty() {
+                    println!("No Setups created yet, try creating one first.");
+                    continue;
+                }
+                println!("Which created setup do you wish to export?  Choose the index.");
+                let index = read_user_input().parse::<usize>().unwrap();
+                println!("Input file name to save to, without extension.");
+                let name = read_user_input();
+                let setup = &setups[index];
+                println!("Schematic version (2: WorldEdit 7.2 and older, 3: WorldEdit 7.3 and newer)");
+                let version = match read_user_input().as_ref() {
+                    "3" => schematic::SchematicVersion::V3,
+                    _ => schematic::SchematicVersion::V2,
+                };
+                for (index, turbine) in setup.turbines().iter().enumerate() {
+                    let turbine_path = if index == 0 {
+                        format!("{name}_turbine.schem")
+                    } else {
+                        format!("{name}_turbine_{}.schem", index + 1)
+                    };
+                    export_schematic(&layout::turbine_layout(turbine), &turbine_path, version);
+                }
+                export_schematic(&layout::fission_layout(setup.reactor()), &format!("{name}_reactor.schem"), version);
+                if let Some(boiler) = setup.boiler() {
+                    export_schematic(&layout::boiler_layout(boiler), &format!("{name}_boiler.schem"), version);
+                }
+            },
+            "a" => {
+                if setups.is_empty() {
+                    println!("No Setups created yet, try creating one first.");
+                    continue;
+                }
+                println!("Which created setup do you wish to analyze?  Choose the index.");
+                let index = read_user_input().parse::<usize>().unwrap();
+                for turbine in setups[index].turbines() {
+                    println!("Upgrades for {}", turbine.summarize());
+                    let upgrades = turbine_analysis::upgrade_turbine(turbine);
+                    if upgrades.is_empty() {
+                        println!("- Nothing left to add without rebuilding\n");
+                    }
+                    for upgrade in upgrades.iter() {
+                        upgrade.print();
+                    }
+                    println!("Adding or removing one part of {}", turbine.summarize());
+                    turbine_analysis::turbine_sensitivity(turbine).print();
+                }
             },
             "m" => println!("{prompt}"),
             "q" => std::process::exit(0),
@@ -88,6 +239,14 @@ fn interactive() {
     }
 }
 
+/// Write a layout to a schematic file, reporting the outcome instead of stopping the REPL
+fn export_schematic(layout: &layout::Layout, path: &str, version: schematic::SchematicVersion) {
+    match schematic::write_schematic(layout, path, version) {
+        Ok(()) => println!("Saved {path}"),
+        Err(error) => println!("Problem writing {path}: {error}"),
+    }
+}
+
 // TODO Make a new function for creating multi setups, Turbines, Reactors
 fn interactive_create() -> Option<setups::SetupType> {
     let prompt = "Create. Options:\n\
@@ -126,25 +285,25 @@ fn interactive_create() -> Option<setups::SetupType> {
 fn interactive_multi_setup() -> Option<setups::SetupType> {
     let prompt = "Create new Mutliblock group setup:\n\
                         b: binary - Two multiblocks attached together\n\
-                        t: trinary - Three multiblock attached setup\n\
+                        t: trinary - Sodium cooled reactor, boiler and turbine\n\
                         q: quit - Return to top menu";
     println!("{prompt}");
     let mut user_input = read_user_input();
     match user_input.as_ref() {
         "b" => {
             // Declare to keep them in scope
-            let turbine;
+            let turbines;
             let reactor;
             println!("Create turbine first (y/n)");
             user_input = read_user_input();
             if user_input.eq("y") {
-                turbine = interactive_turbine();
+                turbines = interactive_turbine();
                 // Ask user if they want to make an encompanting fission reactor
-                println!("Create an optimal fission reactor for this turbine? (y/n)");
+                println!("Create an optimal fission reactor for the turbines? (y/n)");
                 user_input = read_user_input();
                 reactor = if user_input.eq("y") {
-                    //Recommend Fission Reactor based on Turbine
-                    let fuel_assemblies = fission::optimal_fuel_assemblies(&turbine);
+                    //Recommend Fission Reactor based on Turbines
+                    let fuel_assemblies = turbines.iter().map(fission::optimal_fuel_assemblies).sum();
                     fission::turbine_based_fission_reactor(fuel_assemblies)
                 } else {
                     interactive_fission()
@@ -153,22 +312,29 @@ fn interactive_multi_setup() -> Option<setups::SetupType> {
                 reactor = interactive_fission();
                 println!("Create an optimal turbine for this reactor? (y/n)");
                 user_input = read_user_input();
-                turbine = if user_input.eq("y") {
-                    //Recommend Fission Reactor based on Turbine
-                    turbine::turbine_based_on_fission_reactor(reactor.water_burn_rate).unwrap()
+                turbines = if user_input.eq("y") {
+                    //Recommend turbines based on Fission Reactor, splitting the steam if one turbine can't take it all
+                    match turbine::turbine_based_on_fission_reactor(reactor.water_burn_rate) {
+                        Ok(turbine) => vec![turbine],
+                        Err(_) => interactive_split_turbines(reactor.water_burn_rate),
+                    }
                 } else {
                     interactive_turbine()
                 };
             }
-            let binary_setup = setups::BinarySetup { reactor, turbine };
+            let binary_setup = setups::BinarySetup { reactor, turbines };
             return Some(setups::SetupType::BinarySetup(binary_setup));
             // TODO Need to figure out how to represent the type of pipe between the two
         }
         "t" => {
-            let trinary_setup = setups::TrinarySetup {
-                ..Default::default()
-            };
-            return Some(setups::SetupType::TrinarySetup(trinary_setup));
+            let reactor = interactive_fission();
+            match setups::sodium_cooled_setup(&reactor) {
+                Ok(trinary_setup) => {
+                    println!("Succesfully created the sodium cooled setup.");
+                    return Some(setups::SetupType::TrinarySetup(trinary_setup));
+                }
+                Err(error) => println!("Problem designing the sodium cooled setup: {error}"),
+            }
         }
         "q" => println!("Returning"),
         _ => println!("Unrecognized input: '{}'", user_input),
@@ -176,11 +342,77 @@ fn interactive_multi_setup() -> Option<setups::SetupType> {
     return None;
 }
 
-// Turbines
-fn interactive_turbine() -> turbine::Turbine {
+/// Split steam across several turbines, asking what to keep smallest
+fn interactive_split_turbines(water_burn_rate: i32) -> Vec<turbine::Turbine> {
+    println!("Couldn't find one optimal turbine for {water_burn_rate} mB/t, searching for a set of turbines.");
+    println!("Minimize total blocks or total footprint? (b/f)");
+    let objective = if read_user_input().eq("f") {
+        turbine::SplitObjective::Footprint
+    } else {
+        turbine::SplitObjective::Blocks
+    };
+    let turbines = turbine::turbines_based_on_fission_reactor(water_burn_rate, objective).unwrap_or_else(|error| {
+        panic!("Problem creating turbines: {error}");
+    });
+    println!("Using {} turbine(s):", turbines.len());
+    for turbine in turbines.iter() {
+        println!("- {}, {} blocks", turbine.summarize(), turbine.block_count());
+    }
+    turbines
+}
+
+/// Build a steam source, combined sources ask for each part in turn
+fn interactive_steam_source() -> steam::SteamSource {
+    let prompt = "Steam Source. Options:\n\
+                        f: fission - water cooled fission reactor\n\
+                        b: boiler - thermoelectric boiler with superheating elements\n\
+                        h: heated boiler - boiler heated by a fixed amount of heat\n\
+                        u: fusion - water cooled fusion reactor\n\
+                        c: combined - several sources feeding the same turbines";
+    loop {
+        println!("{prompt}");
+        match read_user_input().as_ref() {
+            "f" => return steam::SteamSource::Fission(interactive_fission()),
+            "b" => {
+                println!("Input boiler length.");
+                let x = read_user_input().parse::<i32>().unwrap();
+                println!("Input boiler width.");
+                let z = read_user_input().parse::<i32>().unwrap();
+                println!("Input boiler height.");
+                let y = read_user_input().parse::<i32>().unwrap();
+                println!("Input number of superheating elements.");
+                let heating_element = read_user_input().parse::<i32>().unwrap();
+                return steam::SteamSource::Boiler(boiler::Boiler { x, z, y, heating_element, ..Default::default() });
+            }
+            "h" => {
+                println!("Input heat (J/t) going into the boiler.");
+                let heat = read_user_input().parse::<f64>().unwrap();
+                return steam::SteamSource::HeatedBoiler { heat };
+            }
+            "u" => {
+                println!("Input heat (J/t) the fusion reactor passes to its water.");
+                let heat = read_user_input().parse::<f64>().unwrap();
+                return steam::SteamSource::Fusion { heat };
+            }
+            "c" => {
+                println!("How many sources?");
+                let count = read_user_input().parse::<usize>().unwrap();
+                let sources = (0..count).map(|_| interactive_steam_source()).collect();
+                return steam::SteamSource::Combined(sources);
+            }
+            user_input => println!("Unrecognized input: '{}'", user_input),
+        }
+    }
+}
+
+// Turbines, more than one when the water has to be split across several
+fn interactive_turbine() -> Vec<turbine::Turbine> {
     let prompt = "Turbines Options:\n\
                         o: optimal - optimal based on dimension.\n\
                         f: flow - optimal based on max flow of water\n\
+                        s: steam source - sized for boilers, fusion or several sources combined\n\
+                        p: pareto - pick from every design not beaten on size, blocks and production\n\
+                        c: constraints - best turbine within size, vent, flow and production limits\n\
                         m: manual - get calculations based on already existing turbine.";
     println!("{prompt}");
     let mut user_input = read_user_input();
@@ -196,38 +428,175 @@ fn interactive_turbine() -> turbine::Turbine {
                     panic!("Problem creating turbine: {error}");
                 });
             turbine.print();
-            return turbine;
+            return vec![turbine];
         }
         "f" => {
             println!("What is the max flow (mb/t) of water?");
             let max_flow_rate = read_user_input().parse::<i32>().unwrap();
-            let turbine =
-                turbine::turbine_based_on_fission_reactor(max_flow_rate).unwrap_or_else(|error| {
-                    panic!("Problem creating turbine: {error}");
-                });
-            turbine.print();
-            println!("Create an optimal fission reactor for this turbine? (y/n)");
+            let turbines = match turbine::turbine_based_on_fission_reactor(max_flow_rate) {
+                Ok(turbine) => vec![turbine],
+                Err(_) => interactive_split_turbines(max_flow_rate),
+            };
+            for turbine in turbines.iter() {
+                turbine.print();
+            }
+            println!("Create an optimal fission reactor for these turbines? (y/n)");
             user_input = read_user_input();
             if user_input.eq("y") {
-                let fuel_assemblies = fission::optimal_fuel_assemblies(&turbine);
+                let fuel_assemblies = turbines.iter().map(fission::optimal_fuel_assemblies).sum();
                 let fission_reactor = fission::turbine_based_fission_reactor(fuel_assemblies);
                 fission_reactor.print();
             }
-            return turbine;
+            return turbines;
+        }
+        "s" => {
+            let source = interactive_steam_source();
+            println!("{} makes {} mB/t of steam", source.summarize(), source.output());
+            let turbines = steam::turbines_for_source(&source, turbine::SplitObjective::Blocks).unwrap_or_else(|error| {
+                panic!("Problem creating turbine: {error}");
+            });
+            for turbine in turbines.iter() {
+                turbine.print();
+            }
+            return turbines;
+        }
+        "p" => {
+            println!("What is the max flow (mb/t) of water? (0 for any turbine)");
+            let water_burn_rate = read_user_input().parse::<i32>().unwrap();
+            let turbines = turbine::pareto_turbines_for_flow(water_burn_rate);
+            if turbines.is_empty() {
+                println!("No single turbine can take {water_burn_rate} mB/t.");
+            } else {
+                for (index, turbine) in turbines.iter().enumerate() {
+                    println!("{index}: {} - {} blocks, {:.2} MJ/t", turbine.summarize(), turbine.block_count(), turbine.max_production / 1000000.0);
+                }
+                println!("Which turbine do you want?  Choose the index.");
+                let index = read_user_input().parse::<usize>().unwrap();
+                let turbine = turbines[index].clone();
+                turbine.print();
+                return vec![turbine];
+            }
+        }
+        "c" => {
+            println!("Leave any limit blank for no limit.");
+            println!("Input max turbine length & depth.");
+            let max_length = read_user_input().parse::<i32>().ok();
+            println!("Input max turbine height.");
+            let max_height = read_user_input().parse::<i32>().ok();
+            println!("Input max number of vents.");
+            let max_vents = read_user_input().parse::<i32>().ok();
+            println!("Input min flow (mB/t) of steam it has to take.");
+            let min_flow = read_user_input().parse::<i32>().ok();
+            println!("Input min energy production (MJ/t).");
+            let min_production = read_user_input().parse::<f64>().ok().map(|mega| mega * 1000000.0);
+            let constraints = turbine::TurbineConstraints { max_length, max_height, max_vents, min_flow, min_production };
+            match turbine::design_turbine(&constraints) {
+                Ok(turbine) => {
+                    turbine.print();
+                    return vec![turbine];
+                }
+                Err(error) => println!("Problem designing turbine: {error}"),
+            }
+        }
+        "m" => {
+            println!("Input turbine length & depth.");
+            let x_z = read_user_input().parse::<i32>().unwrap();
+            println!("Input turbine height.");
+            let y = read_user_input().parse::<i32>().unwrap();
+            println!("Input shaft height (number of rotors).");
+            let shaft_height = read_user_input().parse::<i32>().unwrap();
+            println!("Input number of blades.");
+            let blades = read_user_input().parse::<i32>().unwrap();
+            println!("Input number of electromagnetic coils.");
+            let coils = read_user_input().parse::<i32>().unwrap();
+            println!("Input number of vents.");
+            let vents = read_user_input().parse::<i32>().unwrap();
+            println!("Input number of pressure dispersers.");
+            let dispersers = read_user_input().parse::<i32>().unwrap();
+            println!("Input number of saturating condensers.");
+            let condensers = read_user_input().parse::<i32>().unwrap();
+            let turbine = turbine::turbine_factory(x_z, y, condensers, dispersers, vents, shaft_height, blades, coils);
+            for violation in turbine::validate_turbine(&turbine) {
+                println!("Invalid turbine: {violation}");
+            }
+            turbine.print();
+            return vec![turbine];
         }
-        "m" => println!("TODO: Need to add"),
         _ => println!("Unrecognized input: '{}'", user_input),
     }
     // TODO Change this to an option
-    return turbine::Turbine {
+    return vec![turbine::Turbine {
         ..Default::default()
+    }];
+}
+
+fn interactive_turbine_simulation(turbine: &turbine::Turbine) {
+    let prompt = "Steam Profile. Options:\n\
+                        c: constant - same steam every tick.\n\
+                        f: file - stepped steam from a CSV with tick and steam columns.";
+    println!("{prompt}");
+    let user_input = read_user_input();
+    let profile = match user_input.as_ref() {
+        "c" => {
+            println!("Input steam (mB/t).");
+            turbine_simulation::SteamProfile::Constant(read_user_input().parse::<i32>().unwrap())
+        }
+        "f" => {
+            println!("Input path to CSV file.");
+            turbine_simulation::steam_profile_from_csv(&read_user_input()).unwrap_or_else(|error| {
+                panic!("Problem reading steam profile: {error}");
+            })
+        }
+        _ => {
+            println!("Unrecognized input: '{}'", user_input);
+            return;
+        }
+    };
+    println!("Dumping mode (i: idle, d: dumping, e: dumping excess)");
+    let dumping_mode = match read_user_input().as_ref() {
+        "d" => turbine_simulation::DumpingMode::Dumping,
+        "e" => turbine_simulation::DumpingMode::DumpingExcess,
+        _ => turbine_simulation::DumpingMode::Idle,
     };
+    println!("Input energy drawn from the turbine (J/t).");
+    let energy_drain = read_user_input().parse::<f64>().unwrap();
+    println!("Input number of ticks to simulate.");
+    let ticks = read_user_input().parse::<u32>().unwrap();
+    turbine_simulation::simulate_turbine(turbine, &profile, dumping_mode, energy_drain, ticks).print();
+}
+
+fn interactive_fission_simulation(reactor: &FissionReactor) {
+    println!("Input burn rate (mB/t).");
+    let burn = read_user_input().parse::<f64>().unwrap();
+    println!("Input tick the burn rate changes. (blank for never)");
+    let burn_rate = match read_user_input().parse::<u32>() {
+        Ok(tick) => {
+            println!("Input burn rate (mB/t) from then on.");
+            let after = read_user_input().parse::<f64>().unwrap();
+            fission_simulation::RateProfile::Stepped(vec![(0, burn), (tick, after)])
+        }
+        Err(_) => fission_simulation::RateProfile::Constant(burn),
+    };
+    let coolant_name = reactor.coolant.name().to_lowercase();
+    println!("Input {coolant_name} coming in (mB/t). (blank for the reactor's {coolant_name} burn rate)");
+    let flow = read_user_input().parse::<f64>().unwrap_or(reactor.coolant_flow() as f64);
+    println!("Input tick the {coolant_name} stops coming in. (blank for never)");
+    let coolant = match read_user_input().parse::<u32>() {
+        Ok(tick) => fission_simulation::RateProfile::Stepped(vec![(0, flow), (tick, 0.0)]),
+        Err(_) => fission_simulation::RateProfile::Constant(flow),
+    };
+    println!("Input heated coolant drawn from the reactor (mB/t). (blank for all of it)");
+    let heated_coolant_drain = read_user_input().parse::<f64>().unwrap_or(f64::MAX);
+    println!("Input number of ticks to simulate.");
+    let ticks = read_user_input().parse::<u32>().unwrap();
+    fission_simulation::simulate_fission(reactor, &burn_rate, &coolant, heated_coolant_drain, ticks).print();
 }
 
 fn interactive_fission() -> FissionReactor {
     let prompt = "Fission Reactor. Options:\n\
                         o: optimal - optimal based on dimension.\n\
                         f: fuel assemblies - optimal based on number of fuel assemblies.\n\
+                        l: layout - most fuel assemblies for dimensions, searching where stacks go.\n\
                         m: manual - get calculations based on already existing reactor.";
     println!("{prompt}");
     let user_input = read_user_input();
@@ -247,7 +616,46 @@ fn interactive_fission() -> FissionReactor {
             let fuel_assemblies = read_user_input().parse::<i32>().unwrap();
             return fission::turbine_based_fission_reactor(fuel_assemblies);
         }
-        "m" => println!("TODO: Need to add"),
+        "l" => {
+            println!("Input reactor length.");
+            let x = read_user_input().parse::<i32>().unwrap();
+            println!("Input reactor width.");
+            let z = read_user_input().parse::<i32>().unwrap();
+            println!("Input reactor height.");
+            let y = read_user_input().parse::<i32>().unwrap();
+            println!("Input lowest boil efficiency to accept, 0 to 1. (blank for 1)");
+            let min_efficiency = read_user_input().parse::<f32>().unwrap_or(1.0);
+            println!("Input a custom pattern to also try, F for a stack and . for empty, 'done' to finish. (just 'done' to skip)");
+            let custom: Vec<fuel_placement::Pattern> = fuel_placement::parse_pattern(&read_user_lines()).into_iter().collect();
+            match fuel_placement::optimize_fuel_placement(x, z, y, min_efficiency, &custom) {
+                Ok(placement) => {
+                    placement.print();
+                    return placement.reactor;
+                }
+                Err(error) => println!("Invalid reactor: {error}"),
+            }
+        }
+        "m" => {
+            println!("Describe the reactor by (l) layers or (c) column map?");
+            let reactor = if read_user_input().eq("c") {
+                println!("Input the fuel stack height of every column inside the reactor, one row per line, 'done' to finish.");
+                let columns = fission::parse_column_map(&read_user_lines());
+                println!("Input reactor height.");
+                let y = read_user_input().parse::<i32>().unwrap();
+                columns.and_then(|columns| fission::reactor_from_columns(&columns, y))
+            } else {
+                println!("Input each layer inside the reactor bottom first, F fuel, K control rod and . air.");
+                println!("Put a blank line between layers, 'done' to finish.");
+                fission::reactor_from_layers(&read_user_lines())
+            };
+            match reactor {
+                Ok(reactor) => {
+                    reactor.print();
+                    return reactor;
+                }
+                Err(error) => println!("Invalid reactor: {error}"),
+            }
+        }
         _ => println!("Unrecognized input: '{}'", user_input),
     }
     // TODO Change this to an option
@@ -1,4 +1,3 @@
-use num::pow::Pow;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -10,29 +9,49 @@ pub enum Prefix {
     Tera
 }
 
+/// Mekanism's FloatingLong keeps 4 decimal places, truncating anything past them
+pub const FLOATING_LONG_SCALE: i64 = 10000;
+
+/// Slack for values that land a hair under a whole hundredth after float math
+const HUNDREDTHS_EPSILON: f64 = 0.000001;
+
+/// Whole hundredths of a value, the 2 decimals Mekanism shows with the rest truncated
+pub fn to_hundredths(n: f64) -> i64 {
+    (n * 100.0 + HUNDREDTHS_EPSILON).floor() as i64
+}
+
 /// Metric 1 to million conversion
-pub fn convert_to_mega(n: f32) -> f32 {
+pub fn convert_to_mega(n: f64) -> f64 {
     let mega = n / 1000000.0;
     // Appears that Mekanism calc just removes the 3rd decimcal instead of rounding up.
-    (mega * 100.0).floor() / 100.0
+    to_hundredths(mega) as f64 / 100.0
 }
 
 /// Metric 1 to thousand conversion
-pub fn convert_to_kilo(n: f32) -> f32 {
+pub fn convert_to_kilo(n: f64) -> f64 {
     let kilo = n / 1000.0;
     // Appears that Mekanism calc just removes the 3rd decimcal instead of rounding up.
-    (kilo * 100.0).floor() / 100.0
+    to_hundredths(kilo) as f64 / 100.0
 }
 
-pub fn drop_tenth_decimal(n: f32) -> f32 {
-    (n * 10.0).floor() / 10.0
-}
-
-pub fn drop_decimals(n: f32) -> f32 {
-    n.floor()
+/// Energy the way Mekanism displays it, largest prefix below the value and 2 truncated decimals
+pub fn display_energy(joules: f64) -> String {
+    let (prefix, symbol) = if joules >= 1e12 {
+        (Prefix::Tera, "TJ")
+    } else if joules >= 1e9 {
+        (Prefix::Giga, "GJ")
+    } else if joules >= 1e6 {
+        (Prefix::Mega, "MJ")
+    } else if joules >= 1e3 {
+        (Prefix::Kilo, "kJ")
+    } else {
+        (Prefix::Base, "J")
+    };
+    let value = convert_to_prefix(joules, &Prefix::Base, &prefix);
+    format!("{:.2} {}", to_hundredths(value) as f64 / 100.0, symbol)
 }
 
-pub fn convert_to_prefix(n: f32, start_prefix: &Prefix, end_prefix: &Prefix) -> f32 {
+pub fn convert_to_prefix(n: f64, start_prefix: &Prefix, end_prefix: &Prefix) -> f64 {
     let starting_power = match start_prefix {
         self::Prefix::Base => 0,
         self::Prefix::Kilo => 3,
@@ -50,9 +69,9 @@ pub fn convert_to_prefix(n: f32, start_prefix: &Prefix, end_prefix: &Prefix) ->
     let difference: i32 = starting_power - end_power;
     let is_negative = difference < 0;
     if is_negative {
-        return n / 10.pow(difference.abs() as u32) as f32;
+        return n / 10f64.powi(difference.abs());
     }
-    return n * 10.pow(difference.abs() as u32) as f32;
+    n * 10f64.powi(difference.abs())
 } 
 
 #[cfg(test)]
@@ -78,6 +97,21 @@ mod tests {
         let actual = convert_to_prefix(starting, &Mega, &Kilo);
         assert_eq!(expected, actual);
     } 
-    
+
+    #[test]
+    fn test_to_hundredths() {
+        // Truncated like the in game display, not rounded
+        assert_eq!(to_hundredths(182.8352), 18283);
+        assert_eq!(to_hundredths(44.79), 4479);
+        assert_eq!(to_hundredths(133.69), 13369);
+        assert_eq!(convert_to_kilo(182835.2), 182.83);
+    }
+
+    #[test]
+    fn test_display_energy() {
+        assert_eq!(display_energy(182835.2), "182.83 kJ");
+        assert_eq!(display_energy(44797696.0), "44.79 MJ");
+        assert_eq!(display_energy(12.5), "12.50 J");
+    }
 
 }
\ No newline at end of file
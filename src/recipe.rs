@@ -3,21 +3,9 @@ use crate::boiler;
 use crate::turbine;
 use crate::fission;
 use crate::setups;
+use crate::layout;
 
 
-type Block = u16;
-
-enum TurbineBlocks {
-    TurbineCasing(Block),
-    StructuralGlass(Block),
-    TurbineBlade(Block),
-    TurbineRotor(Block),
-    RotationalComplex(Block),
-    ElectromagneticCoil(Block),
-    TurbineVent(Block),
-    SaturatingCondenser(Block)
-}
-
 enum FissionReactorBlocks {
     FissionReactorCasing,
     ReactorGlass,
@@ -31,48 +19,329 @@ enum BoilerBocks {
 
 pub fn turbine_recipe(turbine: &turbine::Turbine) {
     println!("Recipe for {}x{}x{} Turbine", turbine.x_z, turbine.x_z, turbine.y);
-    let saturating_condenser = turbine.condensers as Block;
-    let turbine_vent = turbine.condensers as Block;
-    let electromagnetic_coil = turbine.condensers as Block;
-    let rotational_complex = turbine.condensers as Block;
-    let turbine_rotor = turbine.condensers as Block;
-    let turbine_blade = turbine.condensers as Block;
-    // Saturating Condensers
-    println!("{} Saturating Condenser", saturating_condenser);
-    // Turbine Vent
-    println!("{} Turbine Vent", turbine_vent);
-    // Electromagnetic Coil
-    println!("{} Electromagnetic Coil", electromagnetic_coil);
-    // Rotational Complex
-    println!("{} Rotational Complex", rotational_complex);
-    // Turbine Rotor
-    println!("{} Turbine Rotor", turbine_rotor);
-    // Turbine Blade
-    println!("{} Turbine Blade", turbine_blade);
-    // Structural Glass
-    let structural_glass = turbine_blade * (turbine.x_z - 2) as Block * 4;
-    println!("{} Structural Glass", structural_glass);
-    // Turbine Casng
-    let remaining_y = (turbine.y - turbine.shaft_height) as Block;
-    let side_area = remaining_y * turbine.x_z as Block * 4;
-    let top_bottom_area = ((turbine.x_z - 2).pow(2) * 2) as Block;
-    let turbine_casing = top_bottom_area + side_area - turbine_vent;
-    println!("{} Turbine Casng", turbine_casing);
-
+    let layout = layout::turbine_layout(turbine);
+    for (block, count) in layout.counts() {
+        println!("{} {}", count, block.name());
+    }
+    // Blades are items that go on the rotors, not blocks
+    println!("{} Turbine Blade", turbine.blades);
 }
 
 pub fn fission_reactor_recipe(reactor: &fission::FissionReactor) {
-
+    println!("Recipe for {}x{}x{} Fission Reactor", reactor.x, reactor.z, reactor.y);
+    let layout = layout::fission_layout(reactor);
+    for (block, count) in layout.counts() {
+        println!("{} {}", count, block.name());
+    }
 }
 
 pub fn boiler_recipe(boiler: &boiler::Boiler) {
-
+    println!("Recipe for {}x{}x{} Boiler", boiler.x, boiler.z, boiler.y);
+    let layout = layout::boiler_layout(boiler);
+    for (block, count) in layout.counts() {
+        println!("{} {}", count, block.name());
+    }
 }
 
 pub fn handle_binary_type(binary_type: &setups::BinarySetup) {
-
+    fission_reactor_recipe(&binary_type.reactor);
+    for turbine in binary_type.turbines.iter() {
+        turbine_recipe(turbine);
+    }
 }
 
 pub fn handle_trinary_type(trinary_type: &setups::TrinarySetup){
+    fission_reactor_recipe(&trinary_type.reactor);
+    boiler_recipe(&trinary_type.boiler);
+    for turbine in trinary_type.turbines.iter() {
+        turbine_recipe(turbine);
+    }
+}
+
+const ORE_TO_YELLOWCAKE: f64 = 2.0; // Yellow Cake Uranium per Uranium Ore enriched
+const YELLOWCAKE_TO_OXIDE: f64 = 250.0; // mB of Uranium Oxide per Yellow Cake Uranium oxidized
+const HEXAFLUORIDE_PER_INFUSION: f64 = 2.0; // mB of Uranium Hexafluoride from 1 mB Uranium Oxide and 1 mB Hydrofluoric Acid
+const ENRICHING_TICKS: f64 = 200.0; // Ticks to enrich one item with no speed upgrades
+const OXIDIZING_TICKS: f64 = 100.0; // Ticks to oxidize one item with no speed upgrades
+const MAX_SPEED_UPGRADES: i32 = 8;
+const TICKS_PER_HOUR: f64 = 72000.0;
+const TICKS_PER_DAY: f64 = 24000.0;
+const WASTE_PER_PRODUCT: f64 = 10.0; // mB of Nuclear Waste per mB of Plutonium or Polonium
+const SNA_PEAK_OPERATIONS: f64 = 64.0; // Solar Neutron Activator recipe operations a tick in full sun
+const CRYSTALLIZING_TICKS: f64 = 200.0; // Ticks to crystallize one pellet with no speed upgrades
+const PELLET_GAS: f64 = 1000.0; // mB of gas per pellet
+const SPS_INPUT_PER_ANTIMATTER: f64 = 1000.0; // mB of Polonium per mB of Antimatter
+const SPS_ENERGY_PER_INPUT: f64 = 1000000.0; // J per mB of Polonium
+
+/// Standalone machine or factory tier, factories process one item per slot at the same time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FactoryTier {
+    Machine,
+    Basic,
+    Advanced,
+    Elite,
+    Ultimate,
+}
+
+impl FactoryTier {
+    pub const ALL: [FactoryTier; 5] = [FactoryTier::Machine, FactoryTier::Basic, FactoryTier::Advanced, FactoryTier::Elite, FactoryTier::Ultimate];
+
+    pub fn slots(&self) -> i32 {
+        match self {
+            FactoryTier::Machine => 1,
+            FactoryTier::Basic => 3,
+            FactoryTier::Advanced => 5,
+            FactoryTier::Elite => 7,
+            FactoryTier::Ultimate => 9,
+        }
+    }
+
+    /// In game name of an enriching machine of this tier
+    pub fn enriching_name(&self) -> &'static str {
+        match self {
+            FactoryTier::Machine => "Enrichment Chamber",
+            FactoryTier::Basic => "Basic Enriching Factory",
+            FactoryTier::Advanced => "Advanced Enriching Factory",
+            FactoryTier::Elite => "Elite Enriching Factory",
+            FactoryTier::Ultimate => "Ultimate Enriching Factory",
+        }
+    }
+}
+
+/// Speed upgrades cut an item processing machine's ticks by 10x at the max of 8
+fn upgraded_ticks(base_ticks: f64, speed_upgrades: i32) -> f64 {
+    base_ticks * 10f64.powf(-(speed_upgrades as f64) / MAX_SPEED_UPGRADES as f64)
+}
+
+/// Speed upgrades double what a gas machine like the Chemical Infuser does each tick
+fn upgraded_operations(speed_upgrades: i32) -> f64 {
+    2f64.powi(speed_upgrades)
+}
+
+/// How many of one machine a step needs
+#[derive(Debug, Clone, PartialEq)]
+pub struct MachineCount {
+    pub machine: &'static str,
+    pub count: i32,
+    pub rate: f64,  // Items or mB each machine processes a tick
+}
 
-}
\ No newline at end of file
+/// Everything upstream of a fission reactor burning `burn_rate` mB/t of fissile fuel
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuelSupplyChain {
+    pub burn_rate: f64,  // mB/t
+    pub speed_upgrades: i32,
+    pub ore_per_hour: f64,
+    pub yellowcake_per_hour: f64,
+    pub uranium_oxide: f64,  // mB/t
+    pub hydrofluoric_acid: f64,  // mB/t
+    pub uranium_hexafluoride: f64,  // mB/t
+    pub fissile_fuel: f64,  // mB/t
+    pub machines: Vec<MachineCount>,
+}
+
+impl FuelSupplyChain {
+    pub fn print(&self) {
+        println!("Fuel supply chain for {:.2} mB/t of Fissile Fuel", self.burn_rate);
+        println!("- Uranium Ore {:.0} per hour", self.ore_per_hour);
+        println!("- Yellow Cake Uranium {:.0} per hour", self.yellowcake_per_hour);
+        println!("- Uranium Oxide {:.2} mB/t, with Hydrofluoric Acid {:.2} mB/t", self.uranium_oxide, self.hydrofluoric_acid);
+        println!("- Uranium Hexafluoride {:.2} mB/t", self.uranium_hexafluoride);
+        println!("- Fissile Fuel {:.2} mB/t", self.fissile_fuel);
+        println!("Machines with {} speed upgrades each:", self.speed_upgrades);
+        for machine in self.machines.iter() {
+            println!("- {} {}, {:.3} a tick each", machine.count, machine.machine, machine.rate);
+        }
+        println!();
+    }
+}
+
+/// Uranium ore is enriched into yellow cake, oxidized into uranium oxide, infused with hydrofluoric acid into
+/// uranium hexafluoride, then spun in isotopic centrifuges into fissile fuel, 1000 mB of fuel per ore
+pub fn fuel_supply_chain(burn_rate: f64, tier: FactoryTier, speed_upgrades: i32) -> FuelSupplyChain {
+    let burn_rate = burn_rate.max(0.0);
+    let speed_upgrades = speed_upgrades.clamp(0, MAX_SPEED_UPGRADES);
+    // Centrifuges turn each mB of hexafluoride into a mB of fuel
+    let uranium_hexafluoride = burn_rate;
+    let uranium_oxide = uranium_hexafluoride / HEXAFLUORIDE_PER_INFUSION;
+    let hydrofluoric_acid = uranium_oxide;
+    let yellowcake = uranium_oxide / YELLOWCAKE_TO_OXIDE;  // per tick
+    let ore = yellowcake / ORE_TO_YELLOWCAKE;  // per tick
+
+    let count = |needed: f64, rate: f64| (needed / rate).ceil() as i32;
+    let enriching_rate = tier.slots() as f64 / upgraded_ticks(ENRICHING_TICKS, speed_upgrades);
+    let oxidizing_rate = 1.0 / upgraded_ticks(OXIDIZING_TICKS, speed_upgrades);
+    let infusing_rate = upgraded_operations(speed_upgrades) * HEXAFLUORIDE_PER_INFUSION;
+    let centrifuge_rate = upgraded_operations(speed_upgrades);
+    let machines = vec![
+        MachineCount { machine: tier.enriching_name(), count: count(ore, enriching_rate), rate: enriching_rate },
+        MachineCount { machine: "Chemical Oxidizer", count: count(yellowcake, oxidizing_rate), rate: oxidizing_rate },
+        MachineCount { machine: "Chemical Infuser", count: count(uranium_hexafluoride, infusing_rate), rate: infusing_rate },
+        MachineCount { machine: "Isotopic Centrifuge", count: count(burn_rate, centrifuge_rate), rate: centrifuge_rate },
+    ];
+    FuelSupplyChain {
+        burn_rate,
+        speed_upgrades,
+        ore_per_hour: ore * TICKS_PER_HOUR,
+        yellowcake_per_hour: yellowcake * TICKS_PER_HOUR,
+        uranium_oxide,
+        hydrofluoric_acid,
+        uranium_hexafluoride,
+        fissile_fuel: burn_rate,
+        machines,
+    }
+}
+
+/// Where a reactor's nuclear waste goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WasteRoute {
+    /// Isotopic centrifuges to plutonium, crystallized into plutonium pellets
+    Plutonium,
+    /// Solar neutron activators to polonium, crystallized into polonium pellets
+    PoloniumPellets,
+    /// Solar neutron activators to polonium, the SPS turns it into antimatter, crystallized into antimatter pellets
+    Antimatter,
+}
+
+impl WasteRoute {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WasteRoute::Plutonium => "Plutonium",
+            WasteRoute::PoloniumPellets => "Polonium Pellets",
+            WasteRoute::Antimatter => "Antimatter",
+        }
+    }
+}
+
+/// Everything downstream of a fission reactor making `waste` mB/t of nuclear waste
+#[derive(Debug, Clone, PartialEq)]
+pub struct WasteProcessing {
+    pub waste: f64,  // mB/t
+    pub route: WasteRoute,
+    pub speed_upgrades: i32,
+    pub day_ratio: f64,  // Share of the day solar neutron activators run at peak
+    pub product: f64,  // mB/t of plutonium or polonium, averaged over a day
+    pub antimatter: f64,  // mB/t
+    pub sps_energy: f64,  // J/t
+    pub pellets_per_hour: f64,
+    pub waste_buffer: f64,  // mB of waste to store while the activators wait for the sun
+    pub machines: Vec<MachineCount>,
+}
+
+impl WasteProcessing {
+    pub fn print(&self) {
+        println!("Waste processing of {:.2} mB/t of Nuclear Waste into {}", self.waste, self.route.name());
+        match self.route {
+            WasteRoute::Plutonium => println!("- Plutonium {:.2} mB/t", self.product),
+            WasteRoute::PoloniumPellets | WasteRoute::Antimatter => {
+                println!("- Polonium {:.2} mB/t averaged over a day with sun {:.0}% of it", self.product, self.day_ratio * 100.0);
+                println!("- Store {:.0} mB of waste to get through the night", self.waste_buffer);
+            }
+        }
+        if self.route == WasteRoute::Antimatter {
+            println!("- Antimatter {:.4} mB/t, the SPS needs {:.0} J/t", self.antimatter, self.sps_energy);
+        }
+        println!("- Pellets {:.2} per hour", self.pellets_per_hour);
+        println!("Machines with {} speed upgrades each:", self.speed_upgrades);
+        for machine in self.machines.iter() {
+            println!("- {} {}, {:.3} a tick each", machine.count, machine.machine, machine.rate);
+        }
+        println!();
+    }
+}
+
+/// Size the chain that keeps up with `waste` mB/t of nuclear waste.
+/// Solar neutron activators need a clear view of the sky and only run in daylight, `day_ratio` of the time,
+/// so they're sized to catch up on the night's waste while the sun is out.
+pub fn waste_processing(waste: f64, route: WasteRoute, speed_upgrades: i32, day_ratio: f64) -> WasteProcessing {
+    let waste = waste.max(0.0);
+    let speed_upgrades = speed_upgrades.clamp(0, MAX_SPEED_UPGRADES);
+    let day_ratio = day_ratio.clamp(0.01, 1.0);
+    let count = |needed: f64, rate: f64| (needed / rate).ceil() as i32;
+    let product = waste / WASTE_PER_PRODUCT;
+    let crystallizing_rate = PELLET_GAS / upgraded_ticks(CRYSTALLIZING_TICKS, speed_upgrades);
+    let mut machines = Vec::new();
+    let mut waste_buffer = 0.0;
+    match route {
+        WasteRoute::Plutonium => {
+            let centrifuge_rate = upgraded_operations(speed_upgrades) * WASTE_PER_PRODUCT;
+            machines.push(MachineCount { machine: "Isotopic Centrifuge", count: count(waste, centrifuge_rate), rate: centrifuge_rate });
+        }
+        WasteRoute::PoloniumPellets | WasteRoute::Antimatter => {
+            // Speed upgrades don't fit in an activator
+            let activator_rate = SNA_PEAK_OPERATIONS * WASTE_PER_PRODUCT;
+            machines.push(MachineCount { machine: "Solar Neutron Activator", count: count(waste, activator_rate * day_ratio), rate: activator_rate });
+            waste_buffer = waste * (1.0 - day_ratio) * TICKS_PER_DAY;
+        }
+    }
+    let (antimatter, sps_energy, pellet_gas) = if route == WasteRoute::Antimatter {
+        machines.push(MachineCount { machine: "Supercritical Phase Shifter", count: 1, rate: product });
+        let antimatter = product / SPS_INPUT_PER_ANTIMATTER;
+        (antimatter, product * SPS_ENERGY_PER_INPUT, antimatter)
+    } else {
+        (0.0, 0.0, product)
+    };
+    machines.push(MachineCount { machine: "Chemical Crystallizer", count: count(pellet_gas, crystallizing_rate), rate: crystallizing_rate });
+    WasteProcessing {
+        waste,
+        route,
+        speed_upgrades,
+        day_ratio,
+        product,
+        antimatter,
+        sps_energy,
+        pellets_per_hour: pellet_gas * TICKS_PER_HOUR / PELLET_GAS,
+        waste_buffer,
+        machines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn test_fuel_supply_chain() {
+        // 12 mB/t is 864,000 mB of fuel an hour, 864 ore
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let actual = fuel_supply_chain(reactor.max_burn_rate as f64, FactoryTier::Machine, 0);
+        assert_eq!(actual.ore_per_hour, 864.0);
+        assert_eq!(actual.yellowcake_per_hour, 1728.0);
+        assert_eq!(actual.uranium_oxide, 6.0);
+        assert_eq!(actual.hydrofluoric_acid, 6.0);
+        assert_eq!(actual.uranium_hexafluoride, 12.0);
+        let counts: Vec<i32> = actual.machines.iter().map(|m| m.count).collect();
+        // 0.012 ore a tick at 1 per 200 ticks, 0.024 yellow cake at 1 per 100, 2 mB/t per infuser, 1 per centrifuge
+        assert_eq!(counts, vec![3, 3, 6, 12]);
+        // Fully upgraded, one of each keeps up
+        let actual = fuel_supply_chain(reactor.max_burn_rate as f64, FactoryTier::Ultimate, 8);
+        assert_eq!(actual.machines[0].machine, "Ultimate Enriching Factory");
+        assert!(actual.machines.iter().all(|m| m.count == 1));
+        assert_eq!(fuel_supply_chain(12.0, FactoryTier::Machine, 20), fuel_supply_chain(12.0, FactoryTier::Machine, 8));
+    }
+
+    #[test]
+    fn test_waste_processing() {
+        // 12 mB/t of waste, a 10th of it plutonium and 1,000 mB per pellet
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let waste = reactor.max_burn_rate as f64;  // One mB of waste per mB of fuel
+        let actual = waste_processing(waste, WasteRoute::Plutonium, 0, 0.5);
+        assert_eq!(actual.product, 1.2);
+        assert!((actual.pellets_per_hour - 86.4).abs() < 1e-9);
+        assert_eq!(actual.waste_buffer, 0.0);
+        let counts: Vec<(&str, i32)> = actual.machines.iter().map(|m| (m.machine, m.count)).collect();
+        assert_eq!(counts, vec![("Isotopic Centrifuge", 2), ("Chemical Crystallizer", 1)]);
+        // Antimatter, the activators wait out half the day holding the waste
+        let actual = waste_processing(waste, WasteRoute::Antimatter, 0, 0.5);
+        assert!((actual.antimatter - 0.0012).abs() < 1e-12);
+        assert!((actual.sps_energy - 1200000.0).abs() < 1e-6);
+        assert_eq!(actual.waste_buffer, 144000.0);
+        assert_eq!(actual.machines[0].machine, "Solar Neutron Activator");
+        // Less sun, more activators
+        let sunny = waste_processing(10000.0, WasteRoute::PoloniumPellets, 0, 1.0);
+        let cloudy = waste_processing(10000.0, WasteRoute::PoloniumPellets, 0, 0.25);
+        assert_eq!(sunny.machines[0].count, 16);
+        assert_eq!(cloudy.machines[0].count, 63);
+    }
+}
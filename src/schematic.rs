@@ -0,0 +1,269 @@
+// Export layouts as Sponge schematics (.schem), gzip compressed NBT that WorldEdit and Litematica can paste
+use std::fs::File;
+use std::io::{self, Write};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::layout::{Block, Layout};
+
+/// Minecraft 1.20.1, the version Mekanism 10.4 targets
+const DATA_VERSION: i32 = 3465;
+
+const TAG_END: u8 = 0;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchematicVersion {
+    V2,
+    V3,
+}
+
+/// Block ID used in game for a layout block
+pub fn block_id(block: Block) -> &'static str {
+    match block {
+        Block::Air => "minecraft:air",
+        Block::TurbineCasing => "mekanismgenerators:turbine_casing",
+        Block::StructuralGlass => "mekanism:structural_glass",
+        Block::TurbineValve => "mekanismgenerators:turbine_valve",
+        Block::TurbineVent => "mekanismgenerators:turbine_vent",
+        Block::PressureDisperser => "mekanism:pressure_disperser",
+        Block::TurbineRotor => "mekanismgenerators:turbine_rotor",
+        Block::RotationalComplex => "mekanismgenerators:rotational_complex",
+        Block::ElectromagneticCoil => "mekanismgenerators:electromagnetic_coil",
+        Block::SaturatingCondenser => "mekanismgenerators:saturating_condenser",
+        Block::FissionReactorCasing => "mekanismgenerators:fission_reactor_casing",
+        Block::FissionReactorPort => "mekanismgenerators:fission_reactor_port",
+        Block::FissionFuelAssembly => "mekanismgenerators:fission_fuel_assembly",
+        Block::ControlRodAssembly => "mekanismgenerators:control_rod_assembly",
+        Block::BoilerCasing => "mekanism:boiler_casing",
+        Block::BoilerValve => "mekanism:boiler_valve",
+        Block::SuperheatingElement => "mekanism:superheating_element",
+    }
+}
+
+fn write_tag(buf: &mut Vec<u8>, tag: u8, name: &str) {
+    buf.push(tag);
+    buf.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    buf.extend_from_slice(name.as_bytes());
+}
+
+fn write_short(buf: &mut Vec<u8>, name: &str, value: i16) {
+    write_tag(buf, TAG_SHORT, name);
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn write_int(buf: &mut Vec<u8>, name: &str, value: i32) {
+    write_tag(buf, TAG_INT, name);
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn write_byte_array(buf: &mut Vec<u8>, name: &str, value: &[u8]) {
+    write_tag(buf, TAG_BYTE_ARRAY, name);
+    buf.extend_from_slice(&(value.len() as i32).to_be_bytes());
+    buf.extend_from_slice(value);
+}
+
+fn write_int_array(buf: &mut Vec<u8>, name: &str, value: &[i32]) {
+    write_tag(buf, TAG_INT_ARRAY, name);
+    buf.extend_from_slice(&(value.len() as i32).to_be_bytes());
+    for v in value {
+        buf.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+/// Palette of block IDs and the varint encoded palette index of every block, x fastest then z then y
+fn palette_and_data(layout: &Layout) -> (Vec<Block>, Vec<u8>) {
+    let mut palette: Vec<Block> = Vec::new();
+    let mut data = Vec::new();
+    for y in 0..layout.y {
+        for z in 0..layout.z {
+            for x in 0..layout.x {
+                let block = layout.get(x, y, z);
+                let index = match palette.iter().position(|b| *b == block) {
+                    Some(index) => index,
+                    None => {
+                        palette.push(block);
+                        palette.len() - 1
+                    }
+                };
+                // Varint, 7 bits at a time with the high bit set while more follow
+                let mut value = index as u32;
+                while value >= 0x80 {
+                    data.push((value as u8 & 0x7F) | 0x80);
+                    value >>= 7;
+                }
+                data.push(value as u8);
+            }
+        }
+    }
+    (palette, data)
+}
+
+fn write_palette(buf: &mut Vec<u8>, palette: &[Block]) {
+    write_tag(buf, TAG_COMPOUND, "Palette");
+    for (index, block) in palette.iter().enumerate() {
+        write_int(buf, block_id(*block), index as i32);
+    }
+    buf.push(TAG_END);
+}
+
+/// Uncompressed NBT of a layout as a Sponge schematic
+pub fn schematic_nbt(layout: &Layout, version: SchematicVersion) -> Vec<u8> {
+    let (palette, data) = palette_and_data(layout);
+    let mut buf = Vec::new();
+    match version {
+        SchematicVersion::V2 => {
+            write_tag(&mut buf, TAG_COMPOUND, "Schematic");
+            write_int(&mut buf, "Version", 2);
+            write_int(&mut buf, "DataVersion", DATA_VERSION);
+            write_short(&mut buf, "Width", layout.x as i16);
+            write_short(&mut buf, "Height", layout.y as i16);
+            write_short(&mut buf, "Length", layout.z as i16);
+            write_int_array(&mut buf, "Offset", &[0, 0, 0]);
+            write_int(&mut buf, "PaletteMax", palette.len() as i32);
+            write_palette(&mut buf, &palette);
+            write_byte_array(&mut buf, "BlockData", &data);
+            buf.push(TAG_END);
+        }
+        SchematicVersion::V3 => {
+            write_tag(&mut buf, TAG_COMPOUND, "");
+            write_tag(&mut buf, TAG_COMPOUND, "Schematic");
+            write_int(&mut buf, "Version", 3);
+            write_int(&mut buf, "DataVersion", DATA_VERSION);
+            write_short(&mut buf, "Width", layout.x as i16);
+            write_short(&mut buf, "Height", layout.y as i16);
+            write_short(&mut buf, "Length", layout.z as i16);
+            write_int_array(&mut buf, "Offset", &[0, 0, 0]);
+            write_tag(&mut buf, TAG_COMPOUND, "Blocks");
+            write_palette(&mut buf, &palette);
+            write_byte_array(&mut buf, "Data", &data);
+            buf.push(TAG_END);
+            buf.push(TAG_END);
+            buf.push(TAG_END);
+        }
+    }
+    buf
+}
+
+/// Write a layout to a gzip compressed .schem file
+pub fn write_schematic(layout: &Layout, path: &str, version: SchematicVersion) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder.write_all(&schematic_nbt(layout, version))?;
+    encoder.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::Read;
+    use flate2::read::GzDecoder;
+    use crate::{layout, utils};
+
+    /// Just enough of an NBT reader to get the palette and block data back out
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn take(&mut self, n: usize) -> &'a [u8] {
+            let slice = &self.bytes[self.pos..self.pos + n];
+            self.pos += n;
+            slice
+        }
+
+        fn int(&mut self) -> i32 {
+            i32::from_be_bytes(self.take(4).try_into().unwrap())
+        }
+
+        fn name(&mut self) -> String {
+            let len = u16::from_be_bytes(self.take(2).try_into().unwrap()) as usize;
+            String::from_utf8(self.take(len).to_vec()).unwrap()
+        }
+
+        /// Read a compound's contents, saving every int and byte array by its path
+        fn compound(&mut self, path: &str, ints: &mut HashMap<String, i32>, arrays: &mut HashMap<String, Vec<u8>>) {
+            loop {
+                let tag = self.take(1)[0];
+                if tag == TAG_END {
+                    return;
+                }
+                let name = format!("{path}/{}", self.name());
+                match tag {
+                    TAG_SHORT => { self.take(2); }
+                    TAG_INT => { let value = self.int(); ints.insert(name, value); }
+                    TAG_BYTE_ARRAY => { let len = self.int() as usize; arrays.insert(name, self.take(len).to_vec()); }
+                    TAG_INT_ARRAY => { let len = self.int() as usize; self.take(len * 4); }
+                    TAG_COMPOUND => self.compound(&name, ints, arrays),
+                    _ => panic!("Unexpected tag {tag}"),
+                }
+            }
+        }
+    }
+
+    /// Count of each block ID in a .schem file
+    fn read_block_counts(path: &str, palette_path: &str, data_path: &str) -> HashMap<String, i32> {
+        let mut bytes = Vec::new();
+        GzDecoder::new(File::open(path).unwrap()).read_to_end(&mut bytes).unwrap();
+        let mut reader = Reader { bytes: &bytes, pos: 0 };
+        let mut ints = HashMap::new();
+        let mut arrays = HashMap::new();
+        assert_eq!(reader.take(1)[0], TAG_COMPOUND);
+        let root = reader.name();
+        reader.compound(&root, &mut ints, &mut arrays);
+        let palette: HashMap<i32, String> = ints
+            .iter()
+            .filter_map(|(k, v)| k.strip_prefix(palette_path).map(|id| (*v, id.to_string())))
+            .collect();
+        let mut counts = HashMap::new();
+        let mut value = 0;
+        let mut shift = 0;
+        for byte in arrays[data_path].iter() {
+            value |= ((byte & 0x7F) as i32) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                *counts.entry(palette[&value].clone()).or_insert(0) += 1;
+                value = 0;
+                shift = 0;
+            }
+        }
+        counts
+    }
+
+    fn assert_round_trip(layout: &Layout, name: &str) {
+        let path = std::env::temp_dir().join(name);
+        let path = path.to_str().unwrap();
+        for (version, palette_path, data_path) in [
+            (SchematicVersion::V2, "Schematic/Palette/", "Schematic/BlockData"),
+            (SchematicVersion::V3, "/Schematic/Blocks/Palette/", "/Schematic/Blocks/Data"),
+        ] {
+            write_schematic(layout, path, version).unwrap();
+            let counts = read_block_counts(path, palette_path, data_path);
+            for (block, count) in layout.counts() {
+                assert_eq!(counts[block_id(block)], count);
+            }
+            let total: i32 = counts.values().sum();
+            assert_eq!(total, layout.x * layout.y * layout.z);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_turbine_schematic_round_trip() {
+        let turbine = utils::get_optimal_turbine(9,17);
+        assert_round_trip(&layout::turbine_layout(&turbine), "test_turbine.schem");
+    }
+
+    #[test]
+    fn test_reactor_schematic_round_trip() {
+        let reactor = utils::get_optimal_reactor(7,7,7);
+        assert_round_trip(&layout::fission_layout(&reactor), "test_reactor.schem");
+    }
+}
@@ -1,11 +1,14 @@
 
 //TODO Change file name to multi_setup, better describes what this is.
 
-use crate::{boiler, fission, turbine, recipe};
+use std::cmp::min;
+
+use crate::{boiler, build_guide, fission, layout, metric_prefix, turbine, recipe};
 
 pub trait Setup {
     fn print(&self);
     fn summarize(&self) -> String;
+    fn print_build_guide(&self);
 }
 
 // This file will be more generic and I should use composition when can
@@ -28,10 +31,47 @@ impl Setup for SetupType {
             SetupType::TrinarySetup(trinary_setup) => trinary_setup.summarize(),
         }
     }
+
+    fn print_build_guide(&self) {
+        match self {
+            SetupType::BinarySetup(binary_setup) => binary_setup.print_build_guide(),
+            SetupType::TrinarySetup(trinary_setup) => trinary_setup.print_build_guide(),
+        }
+    }
 }
 
 impl SetupType {
-    fn generate_recipe(&self){
+    pub fn reactor(&self) -> &fission::FissionReactor {
+        match self {
+            SetupType::BinarySetup(binary_setup) => &binary_setup.reactor,
+            SetupType::TrinarySetup(trinary_setup) => &trinary_setup.reactor,
+        }
+    }
+
+    pub fn turbines(&self) -> &[turbine::Turbine] {
+        match self {
+            SetupType::BinarySetup(binary_setup) => &binary_setup.turbines,
+            SetupType::TrinarySetup(trinary_setup) => &trinary_setup.turbines,
+        }
+    }
+
+    /// Boiler between the reactor and turbines, only sodium cooled setups have one
+    pub fn boiler(&self) -> Option<&boiler::Boiler> {
+        match self {
+            SetupType::BinarySetup(_) => None,
+            SetupType::TrinarySetup(trinary_setup) => Some(&trinary_setup.boiler),
+        }
+    }
+
+    /// Energy (J) the setup's turbines make from each mB of fuel burned at `burn_rate` mB/t
+    pub fn energy_per_fuel(&self, burn_rate: f64) -> f64 {
+        match self {
+            SetupType::BinarySetup(binary_setup) => binary_setup.energy_per_fuel(burn_rate),
+            SetupType::TrinarySetup(trinary_setup) => trinary_setup.energy_per_fuel(burn_rate),
+        }
+    }
+
+    pub fn generate_recipe(&self){
         match self {
             SetupType::BinarySetup(binary_setup) => recipe::handle_binary_type(binary_setup),
             SetupType::TrinarySetup(trinary_setup) => recipe::handle_trinary_type(trinary_setup),
@@ -39,44 +79,120 @@ impl SetupType {
     }
 }
 
+/// A fission reactor feeding one or more turbines, large reactors need the steam split
 pub struct BinarySetup {
     pub reactor: crate::fission::FissionReactor,
-    pub turbine: crate::turbine::Turbine
+    pub turbines: Vec<crate::turbine::Turbine>
 }
 
 impl Setup for BinarySetup {
     fn print(&self){
-        self.reactor.print();
-        self.turbine.print();
+        BinarySetup::print(self);
     }
 
     fn summarize(&self) -> String {
-        format!(" {} / {} ",self.reactor.summarize(), self.turbine.summarize())
+        let turbines: Vec<String> = self.turbines.iter().map(|t| t.summarize()).collect();
+        format!(" {} / {} ",self.reactor.summarize(), turbines.join(", "))
+    }
+
+    fn print_build_guide(&self) {
+        println!("{}", self.reactor.summarize());
+        println!("{}", build_guide::build_guide(&layout::fission_layout(&self.reactor)));
+        for turbine in self.turbines.iter() {
+            println!("{}", turbine.summarize());
+            println!("{}", build_guide::build_guide(&layout::turbine_layout(turbine)));
+        }
     }
 }
 
 impl BinarySetup {
     pub fn print(&self){
         self.reactor.print();
-        self.turbine.print();
+        print_turbines(&self.turbines, &self.steam_split());
+        let burn_rate = self.reactor.max_burn_rate as f64;
+        println!("Energy per mB of fuel at {:.2} mB/t: {}\n", burn_rate, metric_prefix::display_energy(self.energy_per_fuel(burn_rate)));
+    }
+
+    /// Reactor steam each turbine gets, split by how much steam each turbine can take
+    pub fn steam_split(&self) -> Vec<i32> {
+        self.steam_split_at(self.reactor.water_burn_rate)
+    }
+
+    /// Split `steam` mB/t across the turbines by how much steam each turbine can take
+    pub fn steam_split_at(&self, steam: i32) -> Vec<i32> {
+        split_steam(&self.turbines, steam)
+    }
+
+    /// Energy (J) the turbines make from each mB of fuel burned at `burn_rate` mB/t
+    pub fn energy_per_fuel(&self, burn_rate: f64) -> f64 {
+        if burn_rate <= 0.0 {
+            return 0.0;
+        }
+        let steam = (burn_rate * fission::FUEL_ASSEMBLY_FLUID_BURN_RATE as f64) as i32;
+        turbines_energy(&self.turbines, &self.steam_split_at(steam)) / burn_rate
     }
 }
 
+/// Split `steam` mB/t across turbines by how much steam each turbine can take
+fn split_steam(turbines: &[turbine::Turbine], steam: i32) -> Vec<i32> {
+    let usable: Vec<i64> = turbines.iter().map(|t| min(t.max_flow, t.max_water_output) as i64).collect();
+    let total: i64 = usable.iter().sum();
+    if total == 0 {
+        return vec![0; turbines.len()];
+    }
+    usable.iter().map(|flow| (steam as i64 * flow / total) as i32).collect()
+}
+
+/// Energy (J/t) the turbines make fed their share of the steam
+fn turbines_energy(turbines: &[turbine::Turbine], steam_split: &[i32]) -> f64 {
+    turbines
+        .iter()
+        .zip(steam_split)
+        .map(|(turbine, steam)| turbine::partial_load(turbine, *steam).energy)
+        .sum()
+}
+
+/// Each turbine with its share of the steam, then the makeup water for all of them
+fn print_turbines(turbines: &[turbine::Turbine], steam_split: &[i32]) {
+    let mut makeup_water = 0.0;
+    for (turbine, steam) in turbines.iter().zip(steam_split) {
+        turbine.print();
+        turbine::partial_load(turbine, *steam).print();
+        let water_loop = turbine::water_loop(turbine, *steam, *steam);
+        water_loop.print();
+        makeup_water += water_loop.makeup_water;
+    }
+    if turbines.len() > 1 {
+        println!("Makeup water for the whole setup {:.0} mB/t\n", makeup_water);
+    }
+}
+
+/// A sodium cooled fission reactor heating a boiler, which feeds one or more turbines
 pub struct TrinarySetup {
     pub reactor: crate::fission::FissionReactor,
-    pub turbine: crate::turbine::Turbine,
+    pub turbines: Vec<crate::turbine::Turbine>,
     pub boiler: crate::boiler::Boiler
 }
 
 impl Setup for TrinarySetup {
     fn print(&self){
-        self.reactor.print();
-        self.boiler.print();
-        self.turbine.print();
+        TrinarySetup::print(self);
     }
 
     fn summarize(&self) -> String{
-        format!(" {} / {} / {}",self.reactor.summarize(), self.boiler.summarize(), self.turbine.summarize())
+        let turbines: Vec<String> = self.turbines.iter().map(|t| t.summarize()).collect();
+        format!(" {} / {} / {}",self.reactor.summarize(), self.boiler.summarize(), turbines.join(", "))
+    }
+
+    fn print_build_guide(&self) {
+        println!("{}", self.reactor.summarize());
+        println!("{}", build_guide::build_guide(&layout::fission_layout(&self.reactor)));
+        println!("{}", self.boiler.summarize());
+        println!("{}", build_guide::build_guide(&layout::boiler_layout(&self.boiler)));
+        for turbine in self.turbines.iter() {
+            println!("{}", turbine.summarize());
+            println!("{}", build_guide::build_guide(&layout::turbine_layout(turbine)));
+        }
     }
 }
 
@@ -84,17 +200,90 @@ impl TrinarySetup {
     pub fn print(&self){
         self.reactor.print();
         self.boiler.print();
-        self.turbine.print();
+        print_turbines(&self.turbines, &self.steam_split());
+        let burn_rate = self.reactor.max_burn_rate as f64;
+        println!("Energy per mB of fuel at {:.2} mB/t: {}\n", burn_rate, metric_prefix::display_energy(self.energy_per_fuel(burn_rate)));
+    }
+
+    /// Steam the boiler sends the turbines, the reactor's heat as steam unless the boiler can't boil that fast
+    pub fn steam(&self) -> i32 {
+        min(self.boiler.max_boil_rate(), self.reactor.water_burn_rate)
+    }
+
+    /// Boiler steam each turbine gets, split by how much steam each turbine can take
+    pub fn steam_split(&self) -> Vec<i32> {
+        self.steam_split_at(self.steam())
+    }
+
+    /// Split `steam` mB/t across the turbines by how much steam each turbine can take
+    pub fn steam_split_at(&self, steam: i32) -> Vec<i32> {
+        split_steam(&self.turbines, steam)
+    }
+
+    /// Energy (J) the turbines make from each mB of fuel burned at `burn_rate` mB/t
+    pub fn energy_per_fuel(&self, burn_rate: f64) -> f64 {
+        if burn_rate <= 0.0 {
+            return 0.0;
+        }
+        let steam = (burn_rate * fission::FUEL_ASSEMBLY_FLUID_BURN_RATE as f64) as i32;
+        turbines_energy(&self.turbines, &self.steam_split_at(min(steam, self.boiler.max_boil_rate()))) / burn_rate
     }
 }
 
+/// Route a reactor's heat through superheated sodium to the smallest boiler that takes it,
+/// then split the steam across as many turbines as it needs
+pub fn sodium_cooled_setup(reactor: &fission::FissionReactor) -> Result<TrinarySetup, &'static str> {
+    let reactor = fission::FissionReactor { coolant: fission::Coolant::Sodium, ..reactor.clone() };
+    let boiler = boiler::boiler_for_heat(reactor.heat_output(), reactor.coolant_flow())?;
+    let steam = min(boiler.max_boil_rate(), reactor.water_burn_rate);
+    let turbines = turbine::turbines_based_on_fission_reactor(steam, turbine::SplitObjective::Blocks)?;
+    Ok(TrinarySetup { reactor, boiler, turbines })
+}
+
 // TODO THis is just for debugging right now
 impl Default for TrinarySetup {
     fn default() -> TrinarySetup {
         TrinarySetup {
             reactor: fission::FissionReactor { ..Default::default() },
             boiler: boiler::Boiler { ..Default::default() },
-            turbine: turbine::Turbine { ..Default::default() }
+            turbines: vec![turbine::Turbine { ..Default::default() }]
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn test_sodium_cooled_setup() {
+        // The boiler hands the turbine the same steam the reactor would have boiled itself
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let actual = sodium_cooled_setup(&reactor).unwrap();
+        assert_eq!(actual.reactor.coolant, fission::Coolant::Sodium);
+        assert_eq!(actual.steam(), reactor.water_burn_rate);
+        assert_eq!(actual.turbines, turbine::turbines_based_on_fission_reactor(reactor.water_burn_rate, turbine::SplitObjective::Blocks).unwrap());
+        assert_eq!(actual.turbines.len(), 1);
+        // Boiler steam split evenly across two matching turbines
+        let turbine = utils::get_optimal_turbine(5,5);
+        let split = TrinarySetup { turbines: vec![turbine.clone(), turbine.clone()], ..actual };
+        assert_eq!(split.steam_split(), vec![120000, 120000]);
+        let expected = turbine::partial_load(&turbine, 120000).energy * 2.0 / 12.0;
+        assert_eq!(split.energy_per_fuel(12.0), expected);
+    }
+
+    #[test]
+    fn test_energy_per_fuel() {
+        // Every mB/t of fuel is 20,000 mB/t of steam into the turbine
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        let turbine = utils::get_optimal_turbine(5,5);
+        let expected = turbine::partial_load(&turbine, 240000).energy / 12.0;
+        let binary = BinarySetup { reactor: reactor.clone(), turbines: vec![turbine] };
+        assert_eq!(binary.energy_per_fuel(12.0), expected);
+        assert_eq!(binary.energy_per_fuel(0.0), 0.0);
+        // The boiler passes the same steam on, so sodium cooling gets the same energy out of fuel
+        let trinary = sodium_cooled_setup(&reactor).unwrap();
+        assert_eq!(trinary.energy_per_fuel(12.0), expected);
+    }
+}
@@ -0,0 +1,103 @@
+// Anything that makes steam for turbines, so turbines can be sized without a fission reactor
+use crate::boiler::Boiler;
+use crate::fission::FissionReactor;
+use crate::turbine::{self, Turbine};
+
+const WATER_THERMAL_ENTHALPY: f64 = 10.0; // J/mB
+const STEAM_ENERGY_EFFICIENCY: f64 = 0.2;
+
+/// Somewhere steam comes from
+#[derive(Debug, Clone)]
+pub enum SteamSource {
+    /// Water cooled fission reactor, boiling its water burn rate
+    Fission(FissionReactor),
+    /// Thermoelectric boiler fed superheated sodium, limited by its superheating elements
+    Boiler(Boiler),
+    /// Thermoelectric boiler heated directly, e.g. by heat generators or resistive heaters, in J/t
+    HeatedBoiler { heat: f64 },
+    /// Water cooled fusion reactor, heat its casing passes to the water in J/t
+    Fusion { heat: f64 },
+    /// Several sources feeding the same turbines
+    Combined(Vec<SteamSource>),
+}
+
+impl SteamSource {
+    /// Steam made every tick in mB/t.
+    /// Mekanism steam carries no temperature, turbines get the same energy from it whatever made it.
+    pub fn output(&self) -> i32 {
+        match self {
+            SteamSource::Fission(reactor) => reactor.water_burn_rate,
+            SteamSource::Boiler(boiler) => boiler.max_boil_rate(),
+            SteamSource::HeatedBoiler { heat } | SteamSource::Fusion { heat } => steam_from_heat(*heat),
+            SteamSource::Combined(sources) => sources.iter().map(|source| source.output()).sum(),
+        }
+    }
+
+    pub fn summarize(&self) -> String {
+        match self {
+            SteamSource::Fission(reactor) => reactor.summarize(),
+            SteamSource::Boiler(boiler) => format!("Boiler with {} superheating elements", boiler.heating_element),
+            SteamSource::HeatedBoiler { heat } => format!("Boiler heated by {heat} J/t"),
+            SteamSource::Fusion { heat } => format!("Fusion Reactor passing {heat} J/t to water"),
+            SteamSource::Combined(sources) => {
+                let summaries: Vec<String> = sources.iter().map(|source| source.summarize()).collect();
+                summaries.join(" + ")
+            }
+        }
+    }
+}
+
+/// Steam(mB/t) = Heat x STEAM_ENERGY_EFFICIENCY(0.2) / WATER_THERMAL_ENTHALPY(10)
+pub fn steam_from_heat(heat: f64) -> i32 {
+    (heat * STEAM_ENERGY_EFFICIENCY / WATER_THERMAL_ENTHALPY).floor() as i32
+}
+
+/// Turbines for a source's steam, one optimal turbine when it fits, otherwise split by the objective
+pub fn turbines_for_source(source: &SteamSource, objective: turbine::SplitObjective) -> Result<Vec<Turbine>, &'static str> {
+    let steam = source.output();
+    match turbine::turbine_based_on_fission_reactor(steam) {
+        Ok(turbine) => Ok(vec![turbine]),
+        Err(_) => turbine::turbines_based_on_fission_reactor(steam, objective),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn test_steam_output() {
+        let reactor = utils::get_optimal_reactor(5,6,5);
+        assert_eq!(SteamSource::Fission(reactor.clone()).output(), reactor.water_burn_rate);
+        // 320,000 mB/t per superheating element
+        let boiler = Boiler { x: 18, z: 18, y: 18, heating_element: 3, ..Default::default() };
+        assert_eq!(SteamSource::Boiler(boiler).output(), 960000);
+        // 5x5x5 only holds 6 blocks of water around the elements, 96,000 mB/t
+        let boiler = Boiler { x: 5, z: 5, y: 5, heating_element: 3, ..Default::default() };
+        assert_eq!(SteamSource::Boiler(boiler).output(), 96000);
+        assert_eq!(SteamSource::HeatedBoiler { heat: 1000000.0 }.output(), 20000);
+        let combined = SteamSource::Combined(vec![
+            SteamSource::Fission(reactor.clone()),
+            SteamSource::Fusion { heat: 1000000.0 },
+        ]);
+        assert_eq!(combined.output(), reactor.water_burn_rate + 20000);
+    }
+
+    #[test]
+    fn test_turbines_for_source() {
+        let turbine = utils::get_optimal_turbine(5,5);
+        // 12 MJ/t boils 240,000 mB/t, enough to need every vent of an optimal 5x5x5
+        let source = SteamSource::HeatedBoiler { heat: 12000000.0 };
+        let actual = turbines_for_source(&source, turbine::SplitObjective::Blocks).unwrap();
+        assert_eq!(actual, vec![turbine]);
+        // 125 elements heat 40,000,000 mB/t, but the 18x18x18 only holds water for 2,096,000 mB/t and one turbine takes that
+        let boiler = Boiler { x: 18, z: 18, y: 18, heating_element: 125, ..Default::default() };
+        let actual = turbines_for_source(&SteamSource::Boiler(boiler.clone()), turbine::SplitObjective::Blocks).unwrap();
+        assert_eq!(actual.len(), 1);
+        // Twenty of them don't fit in one turbine
+        let source = SteamSource::Combined(vec![SteamSource::Boiler(boiler); 20]);
+        let actual = turbines_for_source(&source, turbine::SplitObjective::Blocks).unwrap();
+        assert!(actual.len() > 1);
+    }
+}
@@ -1,5 +1,6 @@
 use log::debug;
 use std::cmp::{max, min};
+use std::fmt;
 use serde::{Deserialize, Serialize};  // TODO Check if we could get Deserialized in dev dependancies
 
 use crate::{fission, metric_prefix};
@@ -9,10 +10,47 @@ use crate::{fission, metric_prefix};
 
 const GENERAL_DISPERSER_GAS_FLOW: i32 = 1280; // mB/t
 const GENERAL_VENT_GAS_FLOW: i32 = 32000; // mB/t
-const GENERAL_CONDENSER_RATE: i32 = 64000; // mB/t
+pub const GENERAL_CONDENSER_RATE: i32 = 64000; // mB/t
 const MAX_ENERGY_PER_STEAM: i32 = 10; // Joules/mB of steam
 const TURBINE_BLADES_PER_COIL: i32 = 4;
+const MAX_BLADES: i32 = 28;
 const GAS_PER_TANK: i32 = 64000; // mB
+const ENERGY_PER_VOLUME: f64 = 16000000.0; // J per block of the whole multiblock
+
+/// Universal cable tiers, each valve pushes energy into one cable
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CableTier {
+    Basic,
+    Advanced,
+    Elite,
+    #[allow(dead_code)]
+    Ultimate,
+}
+
+impl CableTier {
+    #[allow(dead_code)]
+    pub const ALL: [CableTier; 4] = [CableTier::Basic, CableTier::Advanced, CableTier::Elite, CableTier::Ultimate];
+
+    /// Energy a cable carries in J/t
+    pub fn throughput(&self) -> f64 {
+        match self {
+            CableTier::Basic => 8000.0,
+            CableTier::Advanced => 128000.0,
+            CableTier::Elite => 1024000.0,
+            CableTier::Ultimate => 8192000.0,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn name(&self) -> &'static str {
+        match self {
+            CableTier::Basic => "Basic",
+            CableTier::Advanced => "Advanced",
+            CableTier::Elite => "Elite",
+            CableTier::Ultimate => "Ultimate",
+        }
+    }
+}
 
 /// Struct Turbine
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -25,10 +63,12 @@ pub struct Turbine {
     pub shaft_height: i32,
     pub blades: i32,
     pub coils: i32,
-    pub capacity: i32,
+    pub capacity: i32,  // mB of steam
+    #[serde(default)]
+    pub energy_capacity: f64,  // J
     pub max_flow: i32,
     pub tank_volume: i32,
-    pub max_production: f32,
+    pub max_production: f64,
     pub max_water_output: i32,
     //TODO Might need to break every water or energy value into type and value
     pub energy_si_prefix: metric_prefix::Prefix
@@ -46,6 +86,7 @@ impl Default for Turbine {
             blades: 0,
             coils: 0,
             capacity: 0,
+            energy_capacity: 0.0,
             max_flow: 0,
             tank_volume: 0,
             max_production: 0.0,
@@ -69,9 +110,8 @@ impl PartialEq for Turbine {
         self.capacity == other.capacity &&
         self.max_flow == other.max_flow &&
         self.tank_volume == other.tank_volume &&
-        // 5x5x5 turbine in game has 182.93 kJ where formula returns 182.95.  Drop Accuracy for now.
-        // 9x9x17 turbine in game has 44,79, where fomular returned 44.8, dropping all decimals for now
-        metric_prefix::drop_decimals(converted_max_production) == metric_prefix::drop_decimals(other.max_production) &&
+        // In game values are shown to two truncated decimals
+        metric_prefix::to_hundredths(converted_max_production) == metric_prefix::to_hundredths(other.max_production) &&
         self.max_water_output == other.max_water_output
     }
 }
@@ -84,12 +124,47 @@ impl Turbine {
         println!("- Dispersers: {}", self.dispersers);
         println!("- Condensers: {}", self.condensers);
         println!("- Max Flow Rate {} mB/t, Max Water Output {} mB /t", self.max_flow, self.max_water_output);
-        println!("- Capacity {} mJ, Max Energy Production {} mJ\n", self.capacity, self.max_production);
+        println!("- Capacity {} mB, Max Energy Production {}/t", self.capacity, metric_prefix::display_energy(self.max_production));
+        println!("- Energy Buffer {}, {:.1} s at max production", metric_prefix::display_energy(self.energy_capacity), self.buffer_seconds());
+        let valves: Vec<String> = CableTier::ALL
+            .iter()
+            .map(|tier| format!("{} {}", self.valves_needed(tier.throughput()), tier.name()))
+            .collect();
+        println!("- Energy Valves Needed by Cable Tier: {}", valves.join(", "));
+        let limits: Vec<&str> = production_limits(self).iter().map(|limit| limit.describe()).collect();
+        println!("- Limited by: {}\n", limits.join(", "));
     }
 
     pub fn summarize(&self) -> String{
         format!("A {}x{}x{} Turbine", self.x_z, self.x_z, self.y)
     }
+
+    /// Seconds of max production the internal energy buffer holds once nothing draws power
+    pub fn buffer_seconds(&self) -> f64 {
+        if self.max_production <= 0.0 {
+            return 0.0;
+        }
+        self.energy_capacity / self.max_production / 20.0
+    }
+
+    /// Valves needed to push max production out, each valve feeding a line that carries `throughput` J/t
+    pub fn valves_needed(&self, throughput: f64) -> i32 {
+        max((self.max_production / throughput).ceil() as i32, 1)
+    }
+
+    /// Blocks the turbine takes up on the ground
+    pub fn footprint(&self) -> i32 {
+        self.x_z.pow(2)
+    }
+
+    /// Blocks needed to build the turbine, the casing shell plus every part inside.
+    /// Blades are items that go on the rotors so they aren't counted.
+    pub fn block_count(&self) -> i32 {
+        let inside = (self.x_z - 2).pow(2) * (self.y - 2);
+        let shell = self.x_z.pow(2) * self.y - inside;
+        // Plus one for the rotational complex in the disperser layer
+        shell + self.shaft_height + self.dispersers + 1 + self.coils + self.condensers
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -99,11 +174,12 @@ struct TurbineFlow {
     condensers: i32,
     max_flow: i32,
     max_water_ouput: i32,
-    max_energy_production: f32,
+    max_energy_production: f64,
 }
 
 ///  Create turbine based on all blocks/parts added.  Mostly for calculating formulas
 #[allow(dead_code)]
+#[allow(clippy::too_many_arguments)]
 pub fn turbine_factory(
     x_z: i32,
     y: i32,
@@ -123,14 +199,140 @@ pub fn turbine_factory(
         shaft_height,
         blades,
         coils,
-        capacity: energy_capacity(x_z, y),  // TODO Wrong capacity is buckets not power
+        capacity: steam_capacity(x_z, shaft_height),
+        energy_capacity: energy_capacity(x_z, y),
         max_flow: calc_max_flow_rate(x_z, shaft_height, vents),
         tank_volume: calc_lower_volume(x_z, shaft_height),
+        max_production: max_energy_prod(blades, coils, x_z, shaft_height, vents),
         max_water_output: max_water_output(condensers),
         ..Default::default()
     }
 }
 
+/// Parts of a turbine that can cap how much steam it uses or how much energy it makes
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ProductionLimit {
+    TankFlow,
+    VentFlow,
+    Coils,
+    Condensers,
+}
+
+impl ProductionLimit {
+    #[allow(dead_code)]
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ProductionLimit::TankFlow => "Tank Flow (dispersers x lower volume)",
+            ProductionLimit::VentFlow => "Vent Flow",
+            ProductionLimit::Coils => "Coils (not enough for the blades)",
+            ProductionLimit::Condensers => "Condensers (can't condense all the steam)",
+        }
+    }
+}
+
+/// Find which limits are capping the turbine's output.
+/// Flow is always capped by either the tank or the vents, coils and condensers only when they're short.
+pub fn production_limits(turbine: &Turbine) -> Vec<ProductionLimit> {
+    let mut limits = Vec::new();
+    let tank_flow = calc_tank_flow_rate(turbine.x_z, turbine.shaft_height);
+    let vent_flow = calc_vent_flow_rate(turbine.vents);
+    if tank_flow <= vent_flow {
+        limits.push(ProductionLimit::TankFlow);
+    }
+    if vent_flow <= tank_flow {
+        limits.push(ProductionLimit::VentFlow);
+    }
+    if turbine.coils * TURBINE_BLADES_PER_COIL < turbine.blades {
+        limits.push(ProductionLimit::Coils);
+    }
+    if max_water_output(turbine.condensers) < calc_max_flow_rate(turbine.x_z, turbine.shaft_height, turbine.vents) {
+        limits.push(ProductionLimit::Condensers);
+    }
+    limits
+}
+
+/// Mekanism rule a turbine breaks, holding what was built and what is allowed
+#[derive(Debug, PartialEq, Clone)]
+pub enum TurbineViolation {
+    LengthOutOfRange { x_z: i32 },
+    EvenLength { x_z: i32 },
+    HeightOutOfRange { y: i32, max: i32 },
+    ShaftOutOfRange { shaft_height: i32, max: i32 },
+    TooManyVents { vents: i32, max: i32 },
+    WrongDispersers { dispersers: i32, expected: i32 },
+    NotEnoughSpace { coils: i32, condensers: i32, space: i32 },
+    TooManyBlades { blades: i32, max: i32 },
+    NotEnoughCoils { blades: i32, coils: i32, required: i32 },
+}
+
+impl fmt::Display for TurbineViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TurbineViolation::LengthOutOfRange { x_z } =>
+                write!(f, "Length and width {x_z} must be between 5 and 17 blocks."),
+            TurbineViolation::EvenLength { x_z } =>
+                write!(f, "Length {x_z} can't be even, otherwise shaft can't be in center."),
+            TurbineViolation::HeightOutOfRange { y, max } =>
+                write!(f, "Height {y} must be between 5 and {max} blocks."),
+            TurbineViolation::ShaftOutOfRange { shaft_height, max } =>
+                write!(f, "Shaft height {shaft_height} must be between 1 and {max}."),
+            TurbineViolation::TooManyVents { vents, max } =>
+                write!(f, "{vents} vents, only room for {max}."),
+            TurbineViolation::WrongDispersers { dispersers, expected } =>
+                write!(f, "{dispersers} pressure dispersers, the layer needs exactly {expected}."),
+            TurbineViolation::NotEnoughSpace { coils, condensers, space } =>
+                write!(f, "{coils} coils and {condensers} condensers don't fit in the {space} blocks above the dispersers."),
+            TurbineViolation::TooManyBlades { blades, max } =>
+                write!(f, "{blades} blades, the shaft only holds {max}."),
+            TurbineViolation::NotEnoughCoils { blades, coils, required } =>
+                write!(f, "{coils} coils can't support {blades} blades, need {required}."),
+        }
+    }
+}
+
+/// Check a turbine against every structural rule, returning all violations found.
+/// An empty list means the turbine can be built.
+pub fn validate_turbine(turbine: &Turbine) -> Vec<TurbineViolation> {
+    let mut violations = Vec::new();
+    if turbine.x_z < 5 || 17 < turbine.x_z {
+        violations.push(TurbineViolation::LengthOutOfRange { x_z: turbine.x_z });
+    }
+    if turbine.x_z % 2 == 0 {
+        violations.push(TurbineViolation::EvenLength { x_z: turbine.x_z });
+    }
+    // Maximum total height = min(2xLENGTH-1,18)
+    let max_height = min(2 * turbine.x_z - 1, 18);
+    if turbine.y < 5 || max_height < turbine.y {
+        violations.push(TurbineViolation::HeightOutOfRange { y: turbine.y, max: max_height });
+    }
+    // Maximum shaft height = min(2xLENGTH-5,14) [so blades don't touch sides]
+    let max_shaft_height = min(2 * turbine.x_z - 5, 14);
+    if turbine.shaft_height < 1 || max_shaft_height < turbine.shaft_height {
+        violations.push(TurbineViolation::ShaftOutOfRange { shaft_height: turbine.shaft_height, max: max_shaft_height });
+    }
+    let max_vents = calc_max_vents(turbine.x_z, turbine.y, turbine.shaft_height);
+    if turbine.vents > max_vents {
+        violations.push(TurbineViolation::TooManyVents { vents: turbine.vents, max: max_vents });
+    }
+    let expected_dispersers = calc_pressure_dispersers(turbine.x_z);
+    if turbine.dispersers != expected_dispersers {
+        violations.push(TurbineViolation::WrongDispersers { dispersers: turbine.dispersers, expected: expected_dispersers });
+    }
+    let space = max((turbine.y - 3 - turbine.shaft_height) * (turbine.x_z - 2).pow(2), 0);
+    if turbine.coils + turbine.condensers > space {
+        violations.push(TurbineViolation::NotEnoughSpace { coils: turbine.coils, condensers: turbine.condensers, space });
+    }
+    let max_blades = turbine.shaft_height * 2;
+    if turbine.blades > max_blades {
+        violations.push(TurbineViolation::TooManyBlades { blades: turbine.blades, max: max_blades });
+    }
+    let required_coils = calc_coils_needed(turbine.blades);
+    if turbine.coils < required_coils {
+        violations.push(TurbineViolation::NotEnoughCoils { blades: turbine.blades, coils: turbine.coils, required: required_coils });
+    }
+    violations
+}
+
 // Return most optimal turbine based on number of fuel assemblies of existing fission reactor
 // pub fn turbine_based_on_fission_reactor(water_burn_rate: i32) -> Result<Turbine, &'static str> {
 //     let mut turbine: Turbine = Turbine { ..Default::default() };
@@ -190,54 +392,54 @@ pub fn turbine_based_on_fission_reactor(water_burn_rate: i32) -> Result<Turbine,
             temp_turbine.blades = shaft_height * 2;
             temp_turbine.coils = calc_coils_needed(temp_turbine.blades);
             temp_turbine.vents = turbine.vents;
-            return temp_turbine;
-            
+            temp_turbine
         }).collect();
-        if shaft_heights.len() == 0 {
+        if shaft_heights.is_empty() {
             println!("all shaft heights is empty.");
             continue;
         }
         all_shaft_heights.append(& mut shaft_heights);
     }
-    // TODO if the vec is empty should error of function
-    if all_shaft_heights.len() == 0 {
-        panic!("No turbines to return!")
+    if all_shaft_heights.is_empty() {
+        return Err("No turbines to return!");
     }
     // For debugging
     for t in all_shaft_heights.iter_mut() {
-        t.y = min_height(t.shaft_height, t.coils, t.condensers, t.x_z, t.vents);
+        // Leave room for the condensers the water burn rate needs, not just the coils
+        t.y = min_height(t.shaft_height, t.coils, required_condensers, t.x_z, t.vents);
         t.max_production = max_energy_prod(t.blades, t.coils, t.x_z, t.shaft_height, t.vents);
         t.max_flow = calc_max_flow_rate(t.x_z, t.shaft_height, t.vents);
         t.condensers = calc_optimal_condensers(t.x_z, t.y, t.shaft_height, t.coils, t.max_flow);
         t.max_water_output = max_water_output(t.condensers);
         t.capacity = steam_capacity(t.x_z, t.shaft_height);
+        t.energy_capacity = energy_capacity(t.x_z, t.y);
         t.tank_volume = calc_lower_volume(t.x_z, t.shaft_height);
     }
     for t in all_shaft_heights.iter() {
-        println!("Turbines {:?}", t);
+        debug!("Turbines {:?}", t);
     }
-    // Remove turbines that don't meet min max flow, output, or condensers
+    // Remove turbines that don't fit in their max height, or don't meet min max flow, output, or condensers
     all_shaft_heights.retain( | t | {
-        let (best_vent_count, _best_energy_count) = best_vent_count(&t);
-
+        t.y > 0 &&
         min(t.max_flow, t.max_water_output) >= water_burn_rate &&
-        t.condensers >= required_condensers &&
-        best_vent_count <= t.vents
+        t.condensers >= required_condensers
     });
+    // Remove turbines that could use more vents than the flow needs. When a flow falls between sizes every
+    // turbine could, so keep the ones needing the fewest extra vents.
+    let fewest_best_vents = all_shaft_heights.iter().map(|t| best_vent_count(t).0).min().unwrap_or(0);
+    let vent_limit = max(turbine.vents, fewest_best_vents);
+    all_shaft_heights.retain(|t| best_vent_count(t).0 <= vent_limit);
     // Remove turbines that make less power and are larger than smaller more efficient turbines.
     let mut index_to_remove = Vec::new();
-    'outer: for i in 1..all_shaft_heights.len() {
+    'outer: for i in 0..all_shaft_heights.len() {
         let turbine = &all_shaft_heights[i];
-        'inner: for j in 1..all_shaft_heights.len() {
+        for (j, other_turbine) in all_shaft_heights.iter().enumerate() {
             if i == j {
-                continue 'inner;
+                continue;
             }
-            let other_turbine = &all_shaft_heights[j];
-            if turbine.x_z > other_turbine.x_z {
-                if turbine.max_production < other_turbine.max_production {
-                    index_to_remove.push(i);
-                    continue 'outer;
-                }
+            if turbine.x_z > other_turbine.x_z && turbine.max_production < other_turbine.max_production {
+                index_to_remove.push(i);
+                continue 'outer;
             }
         }
     }
@@ -245,14 +447,18 @@ pub fn turbine_based_on_fission_reactor(water_burn_rate: i32) -> Result<Turbine,
         all_shaft_heights.remove(*i);
     }
     
-    let best_turbine = all_shaft_heights.iter().max_by_key( | turbine | turbine.max_production.round() as i32).unwrap().clone();
+    let best_turbine = match all_shaft_heights.iter().max_by_key( | turbine | turbine.max_production.round() as i32) {
+        Some(turbine) => turbine.clone(),
+        None => return Err("No single turbine can handle the water burn rate, split it across multiple turbines."),
+    };
     debug!("Best turbine:\n{:?}\n-------------------------------------", best_turbine);
     Ok(best_turbine)
 }
 
 
 fn min_height(shaft_height: i32, coils: i32, condensers: i32, x_z: i32, vents: i32)  -> i32 {
-    for y in (shaft_height + 3)..18 {
+    // Maximum total height = min(2xLENGTH-1,18)
+    for y in (shaft_height + 3)..=min(2 * x_z - 1, 18) {
         let upper_y = y - shaft_height - 2;
         let internal_volume = (upper_y - 1) * (x_z - 2).pow(2);
         // Check if internal area big enough for all vents
@@ -272,6 +478,90 @@ fn min_height(shaft_height: i32, coils: i32, condensers: i32, x_z: i32, vents: i
 //FLOW = min(1, TURBINE_STORED_AMOUNT / MAX_RATE) *
 //          (TURBINE_STORED_AMOUNT/TURBINE_MAX_STORED_AMOUNT) * MAX_RATE
 
+/// Steady state of a turbine fed a constant amount of steam
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialLoad {
+    pub steam_input: i32,  // mB/t
+    pub stored_steam: f64,  // mB
+    pub flow: f64,  // mB/t
+    pub energy: f64,  // J/t
+}
+
+impl PartialLoad {
+    #[allow(dead_code)]
+    pub fn print(&self) {
+        println!("At {} mB/t of steam:", self.steam_input);
+        println!("- Stored Steam {:.0} mB, Flow {:.0} mB/t", self.stored_steam, self.flow);
+        println!("- Energy Production {:.2} MJ/t\n", self.energy / 1000000.0);
+    }
+}
+
+/// Solve the FLOW formula for the stored steam where flow out matches steam in.
+/// If the input is more than the turbine can ever use the tank fills and flow stays at its max.
+pub fn partial_load(turbine: &Turbine, steam_input: i32) -> PartialLoad {
+    let capacity = steam_capacity(turbine.x_z, turbine.shaft_height) as f64;
+    let max_rate = calc_max_flow_rate(turbine.x_z, turbine.shaft_height, turbine.vents) as f64;
+    let input = max(steam_input, 0) as f64;
+    // A full tank flows at min(STORED, MAX_RATE)
+    let max_steady_flow = max_rate.min(capacity);
+    let (stored_steam, flow) = if input >= max_steady_flow {
+        (capacity, max_steady_flow)
+    } else {
+        // Below MAX_RATE stored: FLOW = STORED² / MAX_STORED, above it: FLOW = STORED * MAX_RATE / MAX_STORED
+        let stored = (input * capacity).sqrt();
+        if stored <= max_rate {
+            (stored, input)
+        } else {
+            (input * capacity / max_rate, input)
+        }
+    };
+    let energy = energy_production(turbine.blades, turbine.coils, flow);
+    PartialLoad { steam_input, stored_steam, flow, energy }
+}
+
+/// Water a turbine's condensers send back to the reactor compared to what the reactor boils
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaterLoop {
+    pub steam_input: i32,  // mB/t
+    pub water_burn_rate: i32,  // mB/t
+    pub water_returned: f64,  // mB/t
+    pub makeup_water: f64,  // mB/t the loop needs from outside
+    pub surplus_water: f64,  // mB/t returned beyond what the reactor boils
+}
+
+impl WaterLoop {
+    /// True when the condensers return all the water the reactor boils
+    pub fn is_self_sustaining(&self) -> bool {
+        self.makeup_water <= 0.0
+    }
+
+    #[allow(dead_code)]
+    pub fn print(&self) {
+        println!("Water loop at {} mB/t of steam:", self.steam_input);
+        println!("- Water Returned {:.0} mB/t of {} mB/t boiled", self.water_returned, self.water_burn_rate);
+        if self.is_self_sustaining() {
+            println!("- Self sustaining, {:.0} mB/t surplus\n", self.surplus_water);
+        } else {
+            println!("- Needs {:.0} mB/t of makeup water\n", self.makeup_water);
+        }
+    }
+}
+
+/// Condensers turn the steam that flows through the turbine back into water, up to max_water_output.
+/// Steam the turbine can't use and the flow beyond the condensers is lost from the loop.
+pub fn water_loop(turbine: &Turbine, steam_input: i32, water_burn_rate: i32) -> WaterLoop {
+    let flow = partial_load(turbine, steam_input).flow;
+    let water_returned = flow.min(turbine.max_water_output as f64);
+    let difference = water_returned - water_burn_rate as f64;
+    WaterLoop {
+        steam_input,
+        water_burn_rate,
+        water_returned,
+        makeup_water: (-difference).max(0.0),
+        surplus_water: difference.max(0.0),
+    }
+}
+
 ///  Return most optimal turbine only based on user inputing dimensions
 pub fn optimal_turbine_with_dimensions(x_z: i32, y: i32) -> Result<Turbine, &'static str> {
     let mut turbine = Turbine { ..Default::default() };
@@ -347,10 +637,233 @@ pub fn optimal_turbine_with_dimensions(x_z: i32, y: i32) -> Result<Turbine, &'st
     turbine.max_production = max_energy_prod(turbine.shaft_height * 2, turbine.coils, x_z, turbine.shaft_height, turbine.vents);
     turbine.max_water_output = max_water_output(turbine.condensers);
     turbine.capacity = steam_capacity(turbine.x_z, turbine.shaft_height);
+    turbine.energy_capacity = energy_capacity(x_z, y);
     Ok(turbine)
 }
 
-fn best_vent_count(turbine: &Turbine) -> (i32, f32) {
+/// Every buildable turbine design, one per length, height and shaft height,
+/// each using its best vent count and optimal condensers
+pub fn turbine_candidates() -> Vec<Turbine> {
+    turbine_candidates_with_max_vents(i32::MAX)
+}
+
+/// Same as `turbine_candidates` but never using more than `max_vents` vents
+fn turbine_candidates_with_max_vents(max_vents: i32) -> Vec<Turbine> {
+    let mut candidates = Vec::new();
+    for x_z in (5..18).step_by(2) {
+        for y in 5..19 {
+            // Maximum shaft height = min(2xLENGTH-5,14) [so blades don't touch sides]
+            for shaft_height in 1..=min(2 * x_z - 5, 14) {
+                let blades = shaft_height * 2;
+                let temp_turbine = Turbine {
+                    x_z,
+                    y,
+                    shaft_height,
+                    blades,
+                    coils: calc_coils_needed(blades),
+                    ..Default::default()
+                };
+                let (best_vents, _best_energy_production) = best_vent_count(&temp_turbine);
+                // Production only goes up with vents until the best count, so capping keeps the best possible
+                let vents = min(best_vents, max_vents);
+                if vents <= 0 {
+                    continue;
+                }
+                let max_flow = calc_max_flow_rate(x_z, shaft_height, vents);
+                let condensers = calc_optimal_condensers(x_z, y, shaft_height, temp_turbine.coils, max_flow);
+                let turbine = turbine_factory(x_z, y, condensers, calc_pressure_dispersers(x_z), vents, shaft_height, blades, temp_turbine.coils);
+                if validate_turbine(&turbine).is_empty() {
+                    candidates.push(turbine);
+                }
+            }
+        }
+    }
+    candidates
+}
+
+/// True if `turbine` is at least as good as `other` at footprint, height, block count and production,
+/// and better at one of them
+fn dominates(turbine: &Turbine, other: &Turbine) -> bool {
+    let no_worse = turbine.footprint() <= other.footprint()
+        && turbine.y <= other.y
+        && turbine.block_count() <= other.block_count()
+        && turbine.max_production >= other.max_production;
+    let better = turbine.footprint() < other.footprint()
+        || turbine.y < other.y
+        || turbine.block_count() < other.block_count()
+        || turbine.max_production > other.max_production;
+    no_worse && better
+}
+
+/// Keep only the turbines no other turbine beats on footprint, height, block count and production.
+/// Returned largest production first.
+pub fn pareto_frontier(turbines: Vec<Turbine>) -> Vec<Turbine> {
+    let mut frontier: Vec<Turbine> = turbines
+        .iter()
+        .filter(|turbine| !turbines.iter().any(|other| dominates(other, turbine)))
+        .cloned()
+        .collect();
+    frontier.sort_by(|a, b| b.max_production.total_cmp(&a.max_production));
+    // Identical designs don't dominate each other
+    frontier.dedup_by(|a, b| a == b);
+    frontier
+}
+
+/// Pareto set of turbines that can take all the water burned by a reactor as steam
+pub fn pareto_turbines_for_flow(water_burn_rate: i32) -> Vec<Turbine> {
+    let candidates = turbine_candidates()
+        .into_iter()
+        .filter(|t| min(t.max_flow, t.max_water_output) >= water_burn_rate)
+        .collect();
+    pareto_frontier(candidates)
+}
+
+/// Limits a designed turbine has to stay within, None for no limit
+#[derive(Debug, Clone, Default)]
+pub struct TurbineConstraints {
+    pub max_length: Option<i32>,
+    pub max_height: Option<i32>,
+    pub max_vents: Option<i32>,
+    pub min_flow: Option<i32>,  // mB/t
+    pub min_production: Option<f64>,  // J/t
+}
+
+/// Constraint that left no turbine to pick from, with the best any remaining turbine could do
+#[derive(Debug, PartialEq, Clone)]
+pub enum ConstraintError {
+    Length { max_length: i32 },
+    Height { max_height: i32 },
+    Vents { max_vents: i32 },
+    Flow { min_flow: i32, best: i32 },
+    Production { min_production: f64, best: f64 },
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstraintError::Length { max_length } =>
+                write!(f, "No turbine fits in a {max_length}x{max_length} footprint, min 5 by 5 blocks."),
+            ConstraintError::Height { max_height } =>
+                write!(f, "No turbine fits in the footprint and is {max_height} blocks or shorter."),
+            ConstraintError::Vents { max_vents } =>
+                write!(f, "A turbine needs at least one vent, {max_vents} allowed."),
+            ConstraintError::Flow { min_flow, best } =>
+                write!(f, "No turbine within the size and vent limits takes {min_flow} mB/t, best is {best} mB/t."),
+            ConstraintError::Production { min_production, best } =>
+                write!(f, "No turbine within the limits produces {}/t, best is {}/t.",
+                    metric_prefix::display_energy(*min_production), metric_prefix::display_energy(*best)),
+        }
+    }
+}
+
+/// Find the highest producing turbine within the constraints, fewest blocks breaking ties.
+/// Constraints are applied size first, then vents, flow and production, the first to leave nothing is returned.
+pub fn design_turbine(constraints: &TurbineConstraints) -> Result<Turbine, ConstraintError> {
+    let max_vents = constraints.max_vents.unwrap_or(i32::MAX);
+    if max_vents < 1 {
+        return Err(ConstraintError::Vents { max_vents });
+    }
+    let mut candidates = turbine_candidates_with_max_vents(max_vents);
+    if let Some(max_length) = constraints.max_length {
+        candidates.retain(|t| t.x_z <= max_length);
+        if candidates.is_empty() {
+            return Err(ConstraintError::Length { max_length });
+        }
+    }
+    if let Some(max_height) = constraints.max_height {
+        candidates.retain(|t| t.y <= max_height);
+        if candidates.is_empty() {
+            return Err(ConstraintError::Height { max_height });
+        }
+    }
+    if let Some(min_flow) = constraints.min_flow {
+        let best = candidates.iter().map(|t| min(t.max_flow, t.max_water_output)).max().unwrap_or(0);
+        candidates.retain(|t| min(t.max_flow, t.max_water_output) >= min_flow);
+        if candidates.is_empty() {
+            return Err(ConstraintError::Flow { min_flow, best });
+        }
+    }
+    if let Some(min_production) = constraints.min_production {
+        let best = candidates.iter().map(|t| t.max_production).fold(0.0, f64::max);
+        candidates.retain(|t| t.max_production >= min_production);
+        if candidates.is_empty() {
+            return Err(ConstraintError::Production { min_production, best });
+        }
+    }
+    let best_turbine = candidates
+        .into_iter()
+        .max_by(|a, b| a.max_production.total_cmp(&b.max_production).then(b.block_count().cmp(&a.block_count())))
+        .unwrap();
+    Ok(best_turbine)
+}
+
+/// What to keep smallest when splitting steam across several turbines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitObjective {
+    Blocks,
+    Footprint,
+}
+
+impl SplitObjective {
+    fn cost(&self, turbine: &Turbine) -> i32 {
+        match self {
+            SplitObjective::Blocks => turbine.block_count(),
+            SplitObjective::Footprint => turbine.footprint(),
+        }
+    }
+}
+
+/// Steam a turbine can take and turn back into water every tick
+fn usable_flow(turbine: &Turbine) -> i32 {
+    min(turbine.max_flow, turbine.max_water_output)
+}
+
+/// Turbines that together take a reactor's water burn rate, for reactors too big for one turbine.
+/// Tries N identical turbines, and N of one turbine plus a smaller one for what's left over,
+/// returning whichever costs the least by the objective. Fewer turbines then more production break ties.
+pub fn turbines_based_on_fission_reactor(water_burn_rate: i32, objective: SplitObjective) -> Result<Vec<Turbine>, &'static str> {
+    if water_burn_rate <= 0 {
+        return Err("Water burn rate must be above 0 mB/t.");
+    }
+    let mut candidates = turbine_candidates();
+    candidates.sort_by_key(|t| objective.cost(t));
+    let mut best: Option<(i32, usize, f64, Vec<Turbine>)> = None;
+    for turbine in candidates.iter() {
+        let flow = usable_flow(turbine) as i64;
+        let rate = water_burn_rate as i64;
+        let identical = ((rate + flow - 1) / flow) as usize;
+        let mut options = vec![vec![turbine.clone(); identical]];
+        // Cheapest turbine taking the remainder, candidates are sorted by cost
+        let remainder = (rate % flow) as i32;
+        if identical > 1 && remainder > 0 {
+            if let Some(smaller) = candidates.iter().find(|t| usable_flow(t) >= remainder) {
+                let mut mixed = vec![turbine.clone(); identical - 1];
+                mixed.push(smaller.clone());
+                options.push(mixed);
+            }
+        }
+        for turbines in options {
+            let cost = turbines.iter().map(|t| objective.cost(t)).sum();
+            let production = turbines.iter().map(|t| t.max_production).sum();
+            let better = match &best {
+                None => true,
+                Some((best_cost, best_count, best_production, _)) => {
+                    (cost, turbines.len()) < (*best_cost, *best_count)
+                        || ((cost, turbines.len()) == (*best_cost, *best_count) && production > *best_production)
+                }
+            };
+            if better {
+                best = Some((cost, turbines.len(), production, turbines));
+            }
+        }
+    }
+    match best {
+        Some((_, _, _, turbines)) => Ok(turbines),
+        None => Err("No turbines to return!"),
+    }
+}
+
+fn best_vent_count(turbine: &Turbine) -> (i32, f64) {
     let mut best_vent_count = 0;
     let mut best_energy_production = 0.0;
     // Find the vent_count/vent flow closest to the tank flow.
@@ -414,7 +927,7 @@ fn calc_lower_volume(x_z: i32, shaft_height: i32) -> i32 {
 
 ///
 #[allow(dead_code)]
-fn calc_coils_needed(num_blades: i32) -> i32 {
+pub fn calc_coils_needed(num_blades: i32) -> i32 {
     max((num_blades as f32 / 4.0).ceil() as i32, 2)
 }
 
@@ -424,11 +937,9 @@ fn calc_pressure_dispersers(x_z: i32) -> i32 {
     (x_z - 2).pow(2) - 1
 }
 
-/// Storing(J) = TurbineWidth² × TurbineHeight × 16,000
-#[allow(dead_code)] 
-fn energy_capacity(x_z: i32, shaft_height: i32) -> i32 {
-    // TODO Move magic number 16000 into const
-    x_z.pow(2) * shaft_height * 16000
+/// Storing(J) = TurbineWidth² × TurbineHeight × ENERGY_PER_VOLUME(16,000,000)
+pub fn energy_capacity(x_z: i32, y: i32) -> f64 {
+    (x_z.pow(2) * y) as f64 * ENERGY_PER_VOLUME
 }
 
 /// Capacity(mB) = TurbineWidth² × TurbineRotorNumber x GAS_PER_TANK(64,000)
@@ -445,21 +956,28 @@ fn steam_capacity(x_z: i32, shaft_height: i32) -> i32 {
 // https://github.com/mekanism/Mekanism/blob/d22f6e2028009ed043f8b40c4ea1f7912be3002c/src/generators/java/mekanism/generators/common/content/turbine/TurbineMultiblockData.java#L244
 ///  Production(J) = maxEnergyPerSteam x BladeRate x SteamFlow
 #[allow(dead_code)]
-fn max_energy_prod(blades: i32, coils: i32, x_z: i32, shaft_height: i32, vents: i32) -> f32 {
-    MAX_ENERGY_PER_STEAM as f32
-        * blade_rate(blades, coils) as f32
-        * calc_max_flow_rate(x_z, shaft_height, vents) as f32
+fn max_energy_prod(blades: i32, coils: i32, x_z: i32, shaft_height: i32, vents: i32) -> f64 {
+    energy_production(blades, coils, calc_max_flow_rate(x_z, shaft_height, vents) as f64)
+}
+
+/// Energy made from `flow` mB of steam, truncated to the 4 decimals of a FloatingLong
+pub fn energy_production(blades: i32, coils: i32, flow: f64) -> f64 {
+    // Multiplier kept in ten thousandths so whole flows multiply exactly
+    (energy_multiplier_units(blades, coils) as f64 * flow).floor() / metric_prefix::FLOATING_LONG_SCALE as f64
 }
 
+/// Joules per mB of steam.
+/// Mekanism divides maxEnergyPerSteam by the max blades as a FloatingLong, which truncates 10/28 to 0.3571,
+/// then multiplies by the blades the coils can spin.
 #[allow(dead_code)]
-fn blade_rate(blades: i32, coils: i32) -> f32 {
-    let blade_rate_1 = blades as f32 / 28.0;
-    let blade_rate_2 = (coils * TURBINE_BLADES_PER_COIL) as f32 / 28.0;
-    if blade_rate_1 < blade_rate_2 {
-        return blade_rate_1;
-    } else {
-        return blade_rate_2;
-    }
+pub fn energy_multiplier(blades: i32, coils: i32) -> f64 {
+    energy_multiplier_units(blades, coils) as f64 / metric_prefix::FLOATING_LONG_SCALE as f64
+}
+
+/// Energy multiplier in ten thousandths of a Joule
+fn energy_multiplier_units(blades: i32, coils: i32) -> i64 {
+    let per_blade = MAX_ENERGY_PER_STEAM as i64 * metric_prefix::FLOATING_LONG_SCALE / MAX_BLADES as i64;
+    per_blade * min(blades, coils * TURBINE_BLADES_PER_COIL) as i64
 }
 
 fn calc_optimal_condensers(x_z: i32, y: i32, shaft_height: i32, coils: i32, max_flow: i32) -> i32 {
@@ -552,7 +1070,7 @@ mod tests {
             shaft_height: 8,
             blades: 16,
             coils: 4,
-            max_production: 44.80, //MJ
+            max_production: 44.79, //MJ
             ..Default::default()
         };
         let actual = max_energy_prod(
@@ -570,7 +1088,7 @@ mod tests {
             shaft_height: 10,
             blades: 20,
             coils: 5,
-            max_production: 133.71, //MJ
+            max_production: 133.69, //MJ
             ..Default::default()
         };
         let actual = max_energy_prod(
@@ -586,16 +1104,195 @@ mod tests {
     #[test]
     fn test_turbine_factory() {
         let actual = turbine_factory(9, 11, 48, 48, 105, 5, 10, 2);
-        // assert_eq!(actual.capacity, 25920000);
+        assert_eq!(actual.capacity, 25920000);
+        assert_eq!(actual.energy_capacity, 14256000000.0);
         assert_eq!(actual.max_flow, 3360000);
         assert_eq!(actual.tank_volume, 405);
         assert_eq!(actual.dispersers, 48);
         assert_eq!(actual.vents, 105);
         assert_eq!(actual.coils, 2);
-        // assert_eq!(actual.max_production, 3.83);
+        assert_eq!(metric_prefix::convert_to_mega(actual.max_production), 9.59);
         assert_eq!(actual.max_water_output, 3072000);
     }
 
+    #[test]
+    fn test_validate_turbine() {
+        // Optimal turbines are always valid
+        let turbine = utils::get_optimal_turbine(9,17);
+        assert_eq!(validate_turbine(&turbine), vec![]);
+        // Every problem is returned at once
+        let turbine = turbine_factory(8, 6, 20, 30, 200, 4, 9, 2);
+        let expected = vec![
+            TurbineViolation::EvenLength { x_z: 8 },
+            TurbineViolation::TooManyVents { vents: 200, max: 0 },
+            TurbineViolation::WrongDispersers { dispersers: 30, expected: 35 },
+            TurbineViolation::NotEnoughSpace { coils: 2, condensers: 20, space: 0 },
+            TurbineViolation::TooManyBlades { blades: 9, max: 8 },
+            TurbineViolation::NotEnoughCoils { blades: 9, coils: 2, required: 3 },
+        ];
+        assert_eq!(validate_turbine(&turbine), expected);
+        // Shaft too tall for the length and turbine too tall
+        let turbine = turbine_factory(5, 11, 0, 8, 8, 6, 12, 3);
+        let violations = validate_turbine(&turbine);
+        assert!(violations.contains(&TurbineViolation::HeightOutOfRange { y: 11, max: 9 }));
+        assert!(violations.contains(&TurbineViolation::ShaftOutOfRange { shaft_height: 6, max: 5 }));
+    }
+
+    #[test]
+    fn test_energy_buffer() {
+        // 5x5x5 holds 2 GJ, 182.83 kJ/t fills it in a bit over 9 minutes
+        let turbine = optimal_turbine_with_dimensions(5, 5).unwrap();
+        assert_eq!(turbine.energy_capacity, 2000000000.0);
+        assert_eq!(turbine.buffer_seconds().floor(), 546.0);
+        // 182.83 kJ/t over 8 kJ/t basic cables
+        assert_eq!(turbine.valves_needed(CableTier::Basic.throughput()), 23);
+        assert_eq!(turbine.valves_needed(CableTier::Advanced.throughput()), 2);
+        assert_eq!(turbine.valves_needed(CableTier::Elite.throughput()), 1);
+        // Energy capacity isn't in the in game data, so it's left out of equality
+        assert_eq!(turbine, utils::get_optimal_turbine(5,5));
+    }
+
+    #[test]
+    fn test_partial_load() {
+        let turbine = utils::get_optimal_turbine(5,5);
+        // Little steam, stored stays under the max rate
+        let actual = partial_load(&turbine, 1000);
+        assert_eq!(actual.stored_steam.round(), 40000.0);
+        assert_eq!(actual.flow, 1000.0);
+        // Half load, stored is over the max rate
+        let actual = partial_load(&turbine, 128000);
+        assert_eq!(actual.stored_steam.round(), 800000.0);
+        assert_eq!(actual.flow, 128000.0);
+        // 2 blades at 0.3571 J/mB each
+        assert_eq!(metric_prefix::to_hundredths(actual.energy), 9141760);
+        // More steam than the turbine can take
+        let actual = partial_load(&turbine, 300000);
+        assert_eq!(actual.stored_steam, turbine.capacity as f64);
+        assert_eq!(actual.flow, turbine.max_flow as f64);
+    }
+
+    #[test]
+    fn test_water_loop() {
+        // 5x5x5 condenses everything it can flow
+        let turbine = utils::get_optimal_turbine(5,5);
+        let actual = water_loop(&turbine, 200000, 200000);
+        assert_eq!(actual.water_returned, 200000.0);
+        assert!(actual.is_self_sustaining());
+        // More steam than the turbine can flow
+        let actual = water_loop(&turbine, 300000, 300000);
+        assert_eq!(actual.water_returned, 256000.0);
+        assert_eq!(actual.makeup_water, 44000.0);
+        assert!(!actual.is_self_sustaining());
+        // 10 condensers only return 640,000 mB/t
+        let turbine = turbine_factory(9, 11, 10, 48, 105, 5, 10, 3);
+        let actual = water_loop(&turbine, 3000000, 3000000);
+        assert_eq!(actual.water_returned, 640000.0);
+        assert_eq!(actual.makeup_water, 2360000.0);
+        // Steam from elsewhere on top of the reactor's
+        let turbine = utils::get_optimal_turbine(5,5);
+        let actual = water_loop(&turbine, 200000, 150000);
+        assert_eq!(actual.surplus_water, 50000.0);
+    }
+
+    #[test]
+    fn test_pareto_frontier() {
+        let small = turbine_factory(5, 5, 4, 8, 8, 1, 2, 2);
+        let tall = turbine_factory(5, 9, 16, 8, 32, 4, 8, 2);
+        // Same production as small but bigger in every way
+        let wasteful = turbine_factory(7, 7, 4, 24, 8, 1, 2, 2);
+        let actual = pareto_frontier(vec![small.clone(), wasteful, tall.clone()]);
+        assert_eq!(actual, vec![tall, small]);
+    }
+
+    #[test]
+    fn test_pareto_turbines_for_flow() {
+        // 5x5x9 is the smallest turbine able to take 1,020,000 mB/t
+        let water_burn_rate = 1020000;
+        let actual = pareto_turbines_for_flow(water_burn_rate);
+        assert!(!actual.is_empty());
+        for turbine in actual.iter() {
+            assert!(min(turbine.max_flow, turbine.max_water_output) >= water_burn_rate);
+            assert!(validate_turbine(turbine).is_empty());
+        }
+        let smallest = actual.iter().min_by_key(|t| (t.footprint(), t.y)).unwrap();
+        assert_eq!((smallest.x_z, smallest.y), (5, 9));
+    }
+
+    #[test]
+    fn test_design_turbine() {
+        // Best within 9x9x12, matches the best of the Pareto set that fits
+        let constraints = TurbineConstraints { max_length: Some(9), max_height: Some(12), ..Default::default() };
+        let actual = design_turbine(&constraints).unwrap();
+        assert!(actual.x_z <= 9 && actual.y <= 12);
+        let best = pareto_frontier(turbine_candidates()).into_iter().find(|t| t.x_z <= 9 && t.y <= 12).unwrap();
+        assert_eq!(actual.max_production, best.max_production);
+        // Capping vents caps production
+        let constraints = TurbineConstraints { max_length: Some(9), max_height: Some(12), max_vents: Some(20), ..Default::default() };
+        let actual = design_turbine(&constraints).unwrap();
+        assert!(actual.vents <= 20);
+        assert!(validate_turbine(&actual).is_empty());
+    }
+
+    #[test]
+    fn test_design_turbine_infeasible() {
+        let constraints = TurbineConstraints { max_length: Some(3), ..Default::default() };
+        assert_eq!(design_turbine(&constraints), Err(ConstraintError::Length { max_length: 3 }));
+        let constraints = TurbineConstraints { max_vents: Some(0), ..Default::default() };
+        assert_eq!(design_turbine(&constraints), Err(ConstraintError::Vents { max_vents: 0 }));
+        // 5x5x5 can't make a megajoule
+        let constraints = TurbineConstraints { max_length: Some(5), max_height: Some(5), min_production: Some(1000000.0), ..Default::default() };
+        match design_turbine(&constraints) {
+            Err(error @ ConstraintError::Production { best, .. }) => {
+                assert_eq!(metric_prefix::convert_to_kilo(best), 182.83);
+                // Same unit the REPL asks for
+                assert_eq!(error.to_string(), "No turbine within the limits produces 1.00 MJ/t, best is 182.83 kJ/t.");
+            }
+            other => panic!("Expected production error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_turbines_based_on_fission_reactor() {
+        // Fits in one 5x5 turbine
+        let actual = turbines_based_on_fission_reactor(100000, SplitObjective::Blocks).unwrap();
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].x_z, 5);
+        // More than the largest turbine can take
+        let water_burn_rate = 40000000;
+        let largest = turbine_candidates().iter().map(usable_flow).max().unwrap();
+        assert!(largest < water_burn_rate);
+        for objective in [SplitObjective::Blocks, SplitObjective::Footprint] {
+            let actual = turbines_based_on_fission_reactor(water_burn_rate, objective).unwrap();
+            assert!(actual.len() >= 2);
+            assert!(actual.iter().map(usable_flow).sum::<i32>() >= water_burn_rate);
+        }
+        let by_blocks = turbines_based_on_fission_reactor(water_burn_rate, SplitObjective::Blocks).unwrap();
+        let by_footprint = turbines_based_on_fission_reactor(water_burn_rate, SplitObjective::Footprint).unwrap();
+        let blocks = |turbines: &Vec<Turbine>| turbines.iter().map(|t| t.block_count()).sum::<i32>();
+        let footprint = |turbines: &Vec<Turbine>| turbines.iter().map(|t| t.footprint()).sum::<i32>();
+        assert!(blocks(&by_blocks) <= blocks(&by_footprint));
+        assert!(footprint(&by_footprint) <= footprint(&by_blocks));
+        assert!(turbines_based_on_fission_reactor(0, SplitObjective::Blocks).is_err());
+    }
+
+    #[test]
+    fn test_turbine_based_on_fission_reactor_too_big() {
+        assert!(turbine_based_on_fission_reactor(40000000).is_err());
+    }
+
+    #[test]
+    fn test_production_limits() {
+        // 9x9x11 with 105 vents, vents cap the flow and 2 coils can't spin 10 blades
+        let actual = turbine_factory(9, 11, 53, 48, 105, 5, 10, 2);
+        assert_eq!(production_limits(&actual), vec![ProductionLimit::VentFlow, ProductionLimit::Coils]);
+        // Too few condensers for the flow
+        let actual = turbine_factory(9, 11, 10, 48, 105, 5, 10, 3);
+        assert_eq!(production_limits(&actual), vec![ProductionLimit::VentFlow, ProductionLimit::Condensers]);
+        // 5x5x5 optimal turbine has tank and vent flow matched
+        let expected = utils::get_optimal_turbine(5,5);
+        assert_eq!(production_limits(&expected), vec![ProductionLimit::TankFlow, ProductionLimit::VentFlow]);
+    }
+
     #[test]
     fn test_optimal_turbine_with_dimensions() {
         // 5x5x5 Turbine
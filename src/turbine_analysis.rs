@@ -0,0 +1,307 @@
+// Ways to grow a turbine that's already built, and which part holds it back
+use std::cmp::min;
+
+use crate::turbine::{self, Turbine, TurbineViolation};
+
+/// Change in each part of a turbine
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PartsDelta {
+    pub y: i32,
+    pub vents: i32,
+    pub condensers: i32,
+    pub coils: i32,
+    pub shaft_height: i32,
+    pub blades: i32,
+}
+
+impl PartsDelta {
+    /// Parts that have to be crafted, vents replace casing so they count too.
+    /// Height is left out, upgrades never change it.
+    pub fn parts_added(&self) -> i32 {
+        [self.vents, self.condensers, self.coils, self.shaft_height, self.blades]
+            .iter()
+            .map(|count| count.max(&0))
+            .sum()
+    }
+
+    pub fn describe(&self) -> String {
+        let parts = [
+            (self.y, "Height"),
+            (self.vents, "Turbine Vent"),
+            (self.condensers, "Saturating Condenser"),
+            (self.coils, "Electromagnetic Coil"),
+            (self.shaft_height, "Turbine Rotor"),
+            (self.blades, "Turbine Blade"),
+        ];
+        let changes: Vec<String> = parts
+            .iter()
+            .filter(|(count, _)| *count != 0)
+            .map(|(count, name)| format!("{:+} {}", count, name))
+            .collect();
+        changes.join(", ")
+    }
+}
+
+/// Recalculate a turbine from its parts, so production is in J/t whatever prefix it was stored with
+fn rebuild(turbine: &Turbine) -> Turbine {
+    turbine::turbine_factory(
+        turbine.x_z,
+        turbine.y,
+        turbine.condensers,
+        turbine.dispersers,
+        turbine.vents,
+        turbine.shaft_height,
+        turbine.blades,
+        turbine.coils,
+    )
+}
+
+/// Rebuild the turbine with the parts changed, None if it couldn't form
+fn apply(turbine: &Turbine, delta: &PartsDelta) -> Option<Turbine> {
+    let changed = turbine::turbine_factory(
+        turbine.x_z,
+        turbine.y + delta.y,
+        turbine.condensers + delta.condensers,
+        turbine.dispersers,
+        turbine.vents + delta.vents,
+        turbine.shaft_height + delta.shaft_height,
+        turbine.blades + delta.blades,
+        turbine.coils + delta.coils,
+    );
+    if changed.vents < 0 || changed.condensers < 0 || changed.coils < 0 || changed.blades < 0 {
+        return None;
+    }
+    // Short on coils still forms, it only caps the blades that spin
+    let forms = turbine::validate_turbine(&changed)
+        .iter()
+        .all(|violation| matches!(violation, TurbineViolation::NotEnoughCoils { .. }));
+    forms.then_some(changed)
+}
+
+/// One way to raise an existing turbine's output
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upgrade {
+    pub name: &'static str,
+    pub delta: PartsDelta,
+    pub turbine: Turbine,
+    pub production_gain: f64,  // J/t
+    pub water_gain: i32,  // mB/t
+}
+
+impl Upgrade {
+    fn new(name: &'static str, original: &Turbine, delta: PartsDelta) -> Option<Upgrade> {
+        let turbine = apply(original, &delta)?;
+        let production_gain = turbine.max_production - original.max_production;
+        let water_gain = turbine.max_water_output - original.max_water_output;
+        if production_gain <= 0.0 && water_gain <= 0 {
+            return None;
+        }
+        Some(Upgrade { name, delta, turbine, production_gain, water_gain })
+    }
+
+    pub fn production_gain_per_part(&self) -> f64 {
+        self.production_gain / self.delta.parts_added() as f64
+    }
+
+    pub fn print(&self) {
+        println!("{}: {}", self.name, self.delta.describe());
+        println!("- Production +{:.2} kJ/t, {:.2} kJ/t per part", self.production_gain / 1000.0, self.production_gain_per_part() / 1000.0);
+        println!("- Water Output +{} mB/t\n", self.water_gain);
+    }
+}
+
+/// Smallest number of extra parts, up to `limit`, that gets the most out of them
+fn best_count(original: &Turbine, limit: i32, delta_for: impl Fn(i32) -> PartsDelta) -> Option<PartsDelta> {
+    let mut best: Option<(Turbine, PartsDelta)> = None;
+    for count in 1..=limit {
+        let delta = delta_for(count);
+        let Some(turbine) = apply(original, &delta) else {
+            break;
+        };
+        let better = match &best {
+            None => true,
+            Some((best_turbine, _)) => turbine.max_production > best_turbine.max_production
+                || turbine.max_water_output > best_turbine.max_water_output,
+        };
+        if better {
+            best = Some((turbine, delta));
+        }
+    }
+    best.map(|(_, delta)| delta)
+}
+
+/// Upgrades that keep the turbine's footprint and height, best production per part first
+pub fn upgrade_turbine(original: &Turbine) -> Vec<Upgrade> {
+    let original = &rebuild(original);
+    let mut upgrades = Vec::new();
+    // Every spot in the upper part of the turbine
+    let upper_limit = (original.x_z - 2).pow(2) * original.y;
+    if let Some(delta) = best_count(original, upper_limit, |vents| PartsDelta { vents, ..Default::default() }) {
+        upgrades.extend(Upgrade::new("More Vents", original, delta));
+    }
+    // Coils to spin every blade already on the shaft
+    let coils = turbine::calc_coils_needed(original.blades) - original.coils;
+    if coils > 0 {
+        upgrades.extend(Upgrade::new("More Coils", original, PartsDelta { coils, ..Default::default() }));
+    }
+    // Fill the shaft with blades
+    let blades = original.shaft_height * 2 - original.blades;
+    if blades > 0 {
+        let coils = turbine::calc_coils_needed(original.blades + blades) - original.coils;
+        upgrades.extend(Upgrade::new("More Blades", original, PartsDelta { blades, coils: coils.max(0), ..Default::default() }));
+    }
+    // A taller shaft, fully bladed and coiled
+    let blades = (original.shaft_height + 1) * 2 - original.blades;
+    let coils = turbine::calc_coils_needed(original.blades + blades) - original.coils;
+    upgrades.extend(Upgrade::new("Taller Shaft", original, PartsDelta { shaft_height: 1, blades, coils: coils.max(0), ..Default::default() }));
+    // Condensers to turn all the flow back into water
+    let condensers = min((original.max_flow as f64 / turbine::GENERAL_CONDENSER_RATE as f64).ceil() as i32 - original.condensers, upper_limit);
+    if let Some(delta) = best_count(original, condensers, |condensers| PartsDelta { condensers, ..Default::default() }) {
+        upgrades.extend(Upgrade::new("More Condensers", original, delta));
+    }
+    upgrades.sort_by(|a, b| {
+        b.production_gain_per_part()
+            .total_cmp(&a.production_gain_per_part())
+            .then(b.water_gain.cmp(&a.water_gain))
+    });
+    upgrades
+}
+
+/// How much one change moves a turbine's numbers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Marginal {
+    pub flow: i32,  // mB/t
+    pub water_output: i32,  // mB/t
+    pub production: f64,  // J/t
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivityRow {
+    pub change: &'static str,
+    pub delta: PartsDelta,
+    /// None when the turbine couldn't form after the change
+    pub marginal: Option<Marginal>,
+}
+
+/// Adding and removing one of each part
+pub struct Sensitivity {
+    pub rows: Vec<SensitivityRow>,
+}
+
+impl Sensitivity {
+    /// The single part that adds the most production, or water output if no part adds production
+    pub fn bottleneck(&self) -> Option<&SensitivityRow> {
+        let additions = || {
+            self.rows
+                .iter()
+                .filter(|row| row.delta.parts_added() > 0 || row.delta.y > 0)
+                .filter_map(|row| row.marginal.map(|marginal| (row, marginal)))
+        };
+        additions()
+            .filter(|(_, marginal)| marginal.production > 0.0)
+            .max_by(|(_, a), (_, b)| a.production.total_cmp(&b.production))
+            .or_else(|| additions().filter(|(_, marginal)| marginal.water_output > 0).max_by_key(|(_, marginal)| marginal.water_output))
+            .map(|(row, _)| row)
+    }
+
+    pub fn print(&self) {
+        println!("{:<18}{:>14}{:>14}{:>20}", "Change", "Flow mB/t", "Water mB/t", "Production kJ/t");
+        for row in self.rows.iter() {
+            match row.marginal {
+                Some(marginal) => println!(
+                    "{:<18}{:>+14}{:>+14}{:>+20.2}",
+                    row.change, marginal.flow, marginal.water_output, marginal.production / 1000.0
+                ),
+                None => println!("{:<18}{:>48}", row.change, "can't form"),
+            }
+        }
+        match self.bottleneck() {
+            Some(row) => println!("Bottleneck: {}\n", row.change),
+            None => println!("Bottleneck: none, no single part helps\n"),
+        }
+    }
+}
+
+/// Marginal change in flow, water output and production from one more or one less of each part
+pub fn turbine_sensitivity(turbine: &Turbine) -> Sensitivity {
+    let original = rebuild(turbine);
+    let changes = [
+        ("+1 Vent", PartsDelta { vents: 1, ..Default::default() }),
+        ("-1 Vent", PartsDelta { vents: -1, ..Default::default() }),
+        ("+1 Condenser", PartsDelta { condensers: 1, ..Default::default() }),
+        ("-1 Condenser", PartsDelta { condensers: -1, ..Default::default() }),
+        ("+1 Coil", PartsDelta { coils: 1, ..Default::default() }),
+        ("-1 Coil", PartsDelta { coils: -1, ..Default::default() }),
+        ("+2 Blades", PartsDelta { blades: 2, ..Default::default() }),
+        ("-2 Blades", PartsDelta { blades: -2, ..Default::default() }),
+        ("+1 Shaft Level", PartsDelta { shaft_height: 1, ..Default::default() }),
+        ("-1 Shaft Level", PartsDelta { shaft_height: -1, ..Default::default() }),
+        ("+1 Height", PartsDelta { y: 1, ..Default::default() }),
+    ];
+    let rows = changes
+        .into_iter()
+        .map(|(change, delta)| {
+            let marginal = apply(&original, &delta).map(|changed| Marginal {
+                flow: changed.max_flow - original.max_flow,
+                water_output: changed.max_water_output - original.max_water_output,
+                production: changed.max_production - original.max_production,
+            });
+            SensitivityRow { change, delta, marginal }
+        })
+        .collect();
+    Sensitivity { rows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn test_upgrade_turbine() {
+        // 9x9x11 with too few vents, coils and condensers
+        let original = turbine::turbine_factory(9, 11, 20, 48, 60, 5, 10, 2);
+        let upgrades = upgrade_turbine(&original);
+        let find = |name| upgrades.iter().find(|upgrade| upgrade.name == name).unwrap();
+        let coils = find("More Coils");
+        assert_eq!(coils.delta, PartsDelta { coils: 1, ..Default::default() });
+        assert!(coils.production_gain > 0.0);
+        // Vents up to the most the upper part holds
+        let vents = find("More Vents");
+        assert_eq!(vents.turbine.vents, 161);
+        // 1,920,000 mB/t flow needs 30 condensers
+        let condensers = find("More Condensers");
+        assert_eq!(condensers.delta.condensers, 10);
+        assert_eq!(condensers.water_gain, 640000);
+        assert_eq!(condensers.production_gain, 0.0);
+        // One coil is the cheapest gain
+        assert_eq!(upgrades[0].name, "More Coils");
+    }
+
+    #[test]
+    fn test_turbine_sensitivity() {
+        // 9x9x11 with 60 vents is vent limited, one more vent adds 32,000 mB/t
+        let original = turbine::turbine_factory(9, 11, 20, 48, 60, 5, 10, 3);
+        let sensitivity = turbine_sensitivity(&original);
+        let find = |change| sensitivity.rows.iter().find(|row| row.change == change).unwrap();
+        let vent = find("+1 Vent").marginal.unwrap();
+        assert_eq!(vent.flow, 32000);
+        assert_eq!(vent.water_output, 0);
+        assert!(vent.production > 0.0);
+        assert_eq!(find("+1 Condenser").marginal.unwrap().water_output, 64000);
+        // 10 blades on a 5 tall shaft is already full
+        assert_eq!(find("+2 Blades").marginal, None);
+        assert_eq!(sensitivity.bottleneck().unwrap().change, "+1 Vent");
+        // Coils limit a turbine with too few of them
+        let original = turbine::turbine_factory(9, 11, 53, 48, 161, 5, 10, 2);
+        let sensitivity = turbine_sensitivity(&original);
+        assert_eq!(sensitivity.bottleneck().unwrap().change, "+1 Coil");
+    }
+
+    #[test]
+    fn test_upgrade_optimal_turbine() {
+        // Nothing left to add inside a 5x5x5
+        let original = utils::get_optimal_turbine(5,5);
+        assert!(upgrade_turbine(&original).is_empty());
+    }
+}
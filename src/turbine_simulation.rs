@@ -0,0 +1,222 @@
+// Tick by tick simulation of a turbine, for ramp up and steam backing up into the reactor
+use std::error::Error;
+use serde::Deserialize;
+
+use crate::turbine::{self, Turbine};
+
+/// Dumping excess keeps the steam tank this full, Mekanism's dumpExcessKeepRatio
+const DUMP_EXCESS_KEEP_RATIO: f64 = 0.85;
+
+/// In game dumping modes of the turbine's steam tank
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DumpingMode {
+    Idle,
+    /// Void up to the turbine's max flow of steam every tick
+    Dumping,
+    /// Void any steam above DUMP_EXCESS_KEEP_RATIO of the tank
+    DumpingExcess,
+}
+
+/// Steam fed into the turbine each tick
+#[derive(Debug, Clone, PartialEq)]
+pub enum SteamProfile {
+    Constant(i32),
+    /// (starting tick, mB/t) pairs in tick order, each rate holds until the next step
+    Stepped(Vec<(u32, i32)>),
+}
+
+impl SteamProfile {
+    /// Steam coming in on the given tick in mB/t
+    pub fn steam_at(&self, tick: u32) -> i32 {
+        match self {
+            SteamProfile::Constant(steam) => *steam,
+            SteamProfile::Stepped(steps) => steps
+                .iter()
+                .take_while(|(start, _)| *start <= tick)
+                .last()
+                .map_or(0, |(_, steam)| *steam),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SteamStep {
+    tick: u32,
+    steam: i32,
+}
+
+/// Read a stepped steam profile from a CSV file with `tick` and `steam` columns
+pub fn steam_profile_from_csv(path: &str) -> Result<SteamProfile, Box<dyn Error>> {
+    let mut rdr = csv::Reader::from_path(path)?;
+    let mut steps = Vec::new();
+    for result in rdr.deserialize() {
+        let step: SteamStep = result?;
+        steps.push((step.tick, step.steam));
+    }
+    steps.sort_by_key(|(tick, _)| *tick);
+    Ok(SteamProfile::Stepped(steps))
+}
+
+/// State of the turbine at the end of a tick
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurbineTick {
+    pub tick: u32,
+    pub steam_input: i32,  // mB/t
+    pub stored_steam: f64,  // mB
+    pub stored_energy: f64,  // J
+    pub flow: f64,  // mB/t
+    pub energy_produced: f64,  // J/t
+    pub water_output: f64,  // mB/t
+    pub dumped_steam: f64,  // mB/t
+    pub backed_up_steam: f64,  // mB/t that couldn't fit in the tank
+}
+
+pub struct TurbineSimulation {
+    pub ticks: Vec<TurbineTick>,
+}
+
+impl TurbineSimulation {
+    /// First tick where the flow caught up with at least 99% of the steam coming in
+    pub fn ramp_up_ticks(&self) -> Option<u32> {
+        self.ticks
+            .iter()
+            .find(|t| t.steam_input > 0 && t.flow >= t.steam_input as f64 * 0.99)
+            .map(|t| t.tick)
+    }
+
+    /// Total steam that couldn't enter the turbine and backed up into the source
+    pub fn total_backed_up_steam(&self) -> f64 {
+        self.ticks.iter().map(|t| t.backed_up_steam).sum()
+    }
+
+    pub fn print(&self) {
+        let Some(last) = self.ticks.last() else {
+            println!("Nothing was simulated.");
+            return;
+        };
+        println!("Simulated {} ticks", self.ticks.len());
+        match self.ramp_up_ticks() {
+            Some(tick) => println!("- Ramped up to steam input after {} ticks ({:.1} s)", tick, tick as f64 / 20.0),
+            None => println!("- Never ramped up to the steam input"),
+        }
+        println!("- Final Stored Steam {:.0} mB, Stored Energy {:.2} MJ", last.stored_steam, last.stored_energy / 1000000.0);
+        println!("- Final Flow {:.0} mB/t, Energy {:.2} MJ/t, Water Output {:.0} mB/t", last.flow, last.energy_produced / 1000000.0, last.water_output);
+        let dumped: f64 = self.ticks.iter().map(|t| t.dumped_steam).sum();
+        println!("- Dumped Steam {:.0} mB", dumped);
+        let backed_up = self.total_backed_up_steam();
+        if backed_up > 0.0 {
+            let first = self.ticks.iter().find(|t| t.backed_up_steam > 0.0).unwrap();
+            println!("- Steam backed up into the source from tick {}, {:.0} mB in total\n", first.tick, backed_up);
+        } else {
+            println!("- No steam backed up into the source\n");
+        }
+    }
+}
+
+/// Step the turbine through `ticks` ticks.
+/// Steam comes in from the profile, the turbine produces energy while there is room in its buffer,
+/// then dumps steam and finally `energy_drain` J/t is pulled out of the buffer.
+pub fn simulate_turbine(
+    turbine: &Turbine,
+    profile: &SteamProfile,
+    dumping_mode: DumpingMode,
+    energy_drain: f64,
+    ticks: u32,
+) -> TurbineSimulation {
+    let steam_capacity = turbine.capacity as f64;
+    let energy_capacity = turbine::energy_capacity(turbine.x_z, turbine.y);
+    let max_rate = turbine.max_flow as f64;
+    let water_capacity = turbine.condensers as f64 * turbine::GENERAL_CONDENSER_RATE as f64;
+    let energy_multiplier = turbine::energy_multiplier(turbine.blades, turbine.coils);
+
+    let mut stored_steam = 0.0;
+    let mut stored_energy = 0.0;
+    let mut history = Vec::with_capacity(ticks as usize);
+    for tick in 1..=ticks {
+        let steam_input = profile.steam_at(tick);
+        // Steam that doesn't fit stays in the source
+        let accepted = (steam_input as f64).min(steam_capacity - stored_steam).max(0.0);
+        let backed_up_steam = steam_input as f64 - accepted;
+        stored_steam += accepted;
+
+        // FLOW = min(STORED, MAX_RATE, ENERGY_NEEDED / MULTIPLIER) * (STORED / MAX_STORED)
+        let energy_needed = energy_capacity - stored_energy;
+        let mut flow = 0.0;
+        if stored_steam > 0.0 && energy_needed > 0.0 && energy_multiplier > 0.0 {
+            let rate = stored_steam.min(max_rate).min(energy_needed / energy_multiplier);
+            flow = (rate * stored_steam / steam_capacity).floor();
+        }
+        let energy_produced = turbine::energy_production(turbine.blades, turbine.coils, flow);
+        stored_energy += energy_produced;
+        stored_steam -= flow;
+        let water_output = flow.min(water_capacity);
+
+        let dumped_steam = match dumping_mode {
+            DumpingMode::Idle => 0.0,
+            DumpingMode::Dumping => stored_steam.min(max_rate),
+            DumpingMode::DumpingExcess => (stored_steam - steam_capacity * DUMP_EXCESS_KEEP_RATIO).max(0.0),
+        };
+        stored_steam -= dumped_steam;
+        stored_energy -= stored_energy.min(energy_drain);
+
+        history.push(TurbineTick {
+            tick,
+            steam_input,
+            stored_steam,
+            stored_energy,
+            flow,
+            energy_produced,
+            water_output,
+            dumped_steam,
+            backed_up_steam,
+        });
+    }
+    TurbineSimulation { ticks: history }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn test_steam_at() {
+        let profile = SteamProfile::Stepped(vec![(10, 1000), (20, 5000)]);
+        assert_eq!(profile.steam_at(1), 0);
+        assert_eq!(profile.steam_at(10), 1000);
+        assert_eq!(profile.steam_at(19), 1000);
+        assert_eq!(profile.steam_at(400), 5000);
+        assert_eq!(SteamProfile::Constant(42).steam_at(7), 42);
+    }
+
+    #[test]
+    fn test_simulate_turbine_ramp_up() {
+        // Energy drained as fast as it's made, so flow settles at the steam input
+        let turbine = utils::get_optimal_turbine(5,5);
+        let profile = SteamProfile::Constant(128000);
+        let actual = simulate_turbine(&turbine, &profile, DumpingMode::Idle, f64::MAX, 2000);
+        let ramp_up = actual.ramp_up_ticks().unwrap();
+        assert!(ramp_up > 1 && ramp_up < 2000);
+        assert_eq!(actual.total_backed_up_steam(), 0.0);
+        let last = actual.ticks.last().unwrap();
+        // Steady state is what's stored when the flow is calculated, before the tick's flow leaves
+        let steady = turbine::partial_load(&turbine, 128000);
+        let stored = last.stored_steam + last.flow;
+        assert!((stored - steady.stored_steam).abs() / steady.stored_steam < 0.01);
+    }
+
+    #[test]
+    fn test_simulate_turbine_backs_up() {
+        // Nothing drains the energy, the 2 GJ buffer fills after ~14,000 ticks then the steam tank fills
+        let turbine = utils::get_optimal_turbine(5,5);
+        let profile = SteamProfile::Constant(200000);
+        let actual = simulate_turbine(&turbine, &profile, DumpingMode::Idle, 0.0, 20000);
+        assert!(actual.total_backed_up_steam() > 0.0);
+        assert_eq!(actual.ticks.last().unwrap().flow, 0.0);
+        // Dumping excess never lets the tank fill
+        let actual = simulate_turbine(&turbine, &profile, DumpingMode::DumpingExcess, 0.0, 20000);
+        assert_eq!(actual.total_backed_up_steam(), 0.0);
+        let last = actual.ticks.last().unwrap();
+        assert!(last.stored_steam <= turbine.capacity as f64 * DUMP_EXCESS_KEEP_RATIO);
+    }
+}